@@ -0,0 +1,250 @@
+//! Content-defined chunking analysis.
+//!
+//! This module estimates how much a gzip archive's *uncompressed* content
+//! would benefit from a CDC-based backup store: it slices the inflated
+//! bytes into content-defined chunks (FastCDC) and reports how many of
+//! those chunks are exact duplicates. It complements the per-member
+//! `ChunkInfo`/`FileSummary` reporting in `printer`, which only describes
+//! gzip member boundaries, not the content inside them.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Deterministic Gear table used by the FastCDC rolling fingerprint.
+///
+/// The values don't need to be cryptographically random, just well mixed,
+/// so cut points aren't correlated with common byte patterns. Generated at
+/// compile time with a splitmix64-style mixer so the table is reproducible.
+const GEAR: [u64; 256] = build_gear_table();
+
+const fn build_gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < 256 {
+        seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        table[i] = z;
+        i += 1;
+    }
+    table
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkAlgorithm {
+    FastCdc,
+}
+
+impl ChunkAlgorithm {
+    fn from_str(s: &str) -> Self {
+        match s {
+            "fastcdc" | "fast-cdc" => ChunkAlgorithm::FastCdc,
+            _ => ChunkAlgorithm::FastCdc,
+        }
+    }
+}
+
+pub struct ChunkerSettings {
+    pub avg_size_kib: usize,
+    pub algorithm: ChunkAlgorithm,
+}
+
+impl ChunkerSettings {
+    /// Parse a `--dedup` argument of the form `AVG_KIB[:ALGORITHM]`,
+    /// e.g. `64` or `64:fastcdc`. Mirrors `ChunkFilterSettings::parse`.
+    pub fn parse(arg: Option<&str>) -> Option<Self> {
+        arg.map(|p| {
+            let parts: Vec<&str> = p.split(':').collect();
+            let avg_size_kib = parts[0].parse().unwrap_or(64);
+            let algorithm = parts
+                .get(1)
+                .map(|s| ChunkAlgorithm::from_str(s))
+                .unwrap_or(ChunkAlgorithm::FastCdc);
+            ChunkerSettings {
+                avg_size_kib,
+                algorithm,
+            }
+        })
+    }
+}
+
+impl Default for ChunkerSettings {
+    fn default() -> Self {
+        ChunkerSettings {
+            avg_size_kib: 64,
+            algorithm: ChunkAlgorithm::FastCdc,
+        }
+    }
+}
+
+/// Chunk-size and dedup stats for a content-defined chunking pass.
+#[derive(Debug)]
+pub struct DedupReport {
+    pub chunk_count: usize,
+    pub avg_chunk_size: f64,
+    pub stddev_chunk_size: f64,
+    pub distinct_chunks: usize,
+    pub distinct_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl DedupReport {
+    pub fn percent_saved(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        let saved = self.total_bytes.saturating_sub(self.distinct_bytes);
+        saved as f64 / self.total_bytes as f64 * 100.0
+    }
+}
+
+impl fmt::Display for DedupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\n🧩 Dedup Analysis:\n")?;
+        write!(f, "├─ 📦 Chunks: {}\n", self.chunk_count)?;
+        write!(
+            f,
+            "├─ 📏 Avg/Stddev Size: {:.0}B / {:.0}B\n",
+            self.avg_chunk_size, self.stddev_chunk_size
+        )?;
+        write!(
+            f,
+            "├─ 🔁 Distinct Chunks: {} ({} unique bytes of {})\n",
+            self.distinct_chunks, self.distinct_bytes, self.total_bytes
+        )?;
+        write!(f, "└─ 💾 Potential Savings: {:.1}%", self.percent_saved())
+    }
+}
+
+/// Cut the input into content-defined chunks and return each chunk's
+/// `(offset, length)`.
+fn fastcdc_boundaries(data: &[u8], avg_size: usize) -> Vec<(usize, usize)> {
+    let avg_size = avg_size.max(64);
+    let min_size = (avg_size / 4).max(1);
+    let max_size = avg_size * 8;
+    let bits = (avg_size as f64).log2().round() as u32;
+    let mask_s: u64 = (1u64 << (bits + 2).min(63)) - 1;
+    let mask_l: u64 = (1u64 << bits.saturating_sub(2)) - 1;
+
+    let mut boundaries = Vec::new();
+    let mut start = 0usize;
+
+    while start < data.len() {
+        let mut fp: u64 = 0;
+        let mut end = data.len();
+        let mut pos = start;
+        while pos < data.len() {
+            let len = pos - start + 1;
+            if len >= max_size {
+                end = pos + 1;
+                break;
+            }
+            fp = (fp << 1).wrapping_add(GEAR[data[pos] as usize]);
+            if len >= min_size {
+                let mask = if len < avg_size { mask_s } else { mask_l };
+                if fp & mask == 0 {
+                    end = pos + 1;
+                    break;
+                }
+            }
+            pos += 1;
+        }
+        boundaries.push((start, end - start));
+        start = end;
+    }
+
+    boundaries
+}
+
+fn hash_chunk(chunk: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    chunk.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Run CDC over `data` and report dedup potential.
+pub fn analyze(data: &[u8], settings: &ChunkerSettings) -> DedupReport {
+    let ChunkAlgorithm::FastCdc = settings.algorithm;
+    let boundaries = fastcdc_boundaries(data, settings.avg_size_kib * 1024);
+
+    let sizes: Vec<usize> = boundaries.iter().map(|(_, len)| *len).collect();
+    let chunk_count = sizes.len();
+    let total_bytes: u64 = sizes.iter().map(|s| *s as u64).sum();
+    let avg_chunk_size = if chunk_count == 0 {
+        0.0
+    } else {
+        total_bytes as f64 / chunk_count as f64
+    };
+    let variance = if chunk_count == 0 {
+        0.0
+    } else {
+        sizes
+            .iter()
+            .map(|s| (*s as f64 - avg_chunk_size).powi(2))
+            .sum::<f64>()
+            / chunk_count as f64
+    };
+    let stddev_chunk_size = variance.sqrt();
+
+    let mut seen = HashSet::new();
+    let mut distinct_bytes = 0u64;
+    for (start, len) in &boundaries {
+        let chunk = &data[*start..*start + *len];
+        if seen.insert(hash_chunk(chunk)) {
+            distinct_bytes += *len as u64;
+        }
+    }
+
+    DedupReport {
+        chunk_count,
+        avg_chunk_size,
+        stddev_chunk_size,
+        distinct_chunks: seen.len(),
+        distinct_bytes,
+        total_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundaries_cover_input_with_no_gaps_or_overlap() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let boundaries = fastcdc_boundaries(&data, 512);
+
+        let mut expected_start = 0;
+        for (start, len) in &boundaries {
+            assert_eq!(*start, expected_start);
+            assert!(*len > 0);
+            expected_start += len;
+        }
+        assert_eq!(expected_start, data.len());
+    }
+
+    #[test]
+    fn empty_input_has_no_boundaries() {
+        assert_eq!(fastcdc_boundaries(&[], 512), Vec::new());
+    }
+
+    #[test]
+    fn identical_repeated_content_is_deduplicated() {
+        let block: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        let mut data = Vec::new();
+        for _ in 0..8 {
+            data.extend_from_slice(&block);
+        }
+        let settings = ChunkerSettings {
+            avg_size_kib: 1,
+            algorithm: ChunkAlgorithm::FastCdc,
+        };
+        let report = analyze(&data, &settings);
+        assert!(report.distinct_chunks < report.chunk_count);
+    }
+}
@@ -1,9 +1,227 @@
-use std::io::{self, Read, Seek, SeekFrom};
-use flate2::read::GzDecoder;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use flate2::{Decompress, FlushDecompress, Status};
 use chrono::DateTime;
-use crate::models::{ChunkInfo, GzipHeaderInfo};
+use crate::index::bgzf_virtual_offset;
+use crate::models::{ChunkInfo, Format, GzipHeaderInfo, GzipValidationError};
+use crate::zlib::{is_zlib_magic, read_zlib_chunk};
 
 const GZIP_HEADER_SIZE: usize = 10;  // Standard GZIP header size
+const GZIP_FOOTER_SIZE: usize = 8;   // CRC32 (4 bytes) + ISIZE (4 bytes)
+
+/// Table-driven IEEE CRC32 (reflected, poly 0xEDB88320), matching the
+/// checksum RFC 1952 requires in every gzip member trailer.
+const CRC32_TABLE: [u32; 256] = build_crc32_table();
+
+const fn build_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        let idx = ((crc ^ byte as u32) & 0xFF) as usize;
+        crc = (crc >> 8) ^ CRC32_TABLE[idx];
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+/// Raw CRC32/ISIZE values from a member's trailer vs. what decompression
+/// actually produced. Exposed on `ChunkInfo` verbatim for callers that want
+/// the numbers rather than a `GzipValidationError` message. Whether the
+/// trailer actually validates is derived from these (and, for BGZF, from
+/// `ChunkInfo::bgzf_block_size`) rather than stored redundantly — see
+/// `trailer_validation_error`.
+#[derive(Debug, Clone, Copy)]
+pub struct TrailerFields {
+    pub crc32_expected: u32,
+    pub crc32_actual: u32,
+    pub isize_expected: u32,
+}
+
+/// Parse the 8-byte CRC32+ISIZE footer and compute the CRC32 actually
+/// produced by decompression. Panics if `footer` is shorter than
+/// `GZIP_FOOTER_SIZE`; callers must check that first.
+pub fn trailer_fields(footer: &[u8], decompressed: &[u8]) -> TrailerFields {
+    TrailerFields {
+        crc32_expected: u32::from_le_bytes(footer[0..4].try_into().unwrap()),
+        crc32_actual: crc32(decompressed),
+        isize_expected: u32::from_le_bytes(footer[4..8].try_into().unwrap()),
+    }
+}
+
+pub(crate) fn trailer_validation_error(fields: &TrailerFields, actual_size: u64) -> Option<GzipValidationError> {
+    if fields.crc32_expected != fields.crc32_actual {
+        return Some(GzipValidationError {
+            claimed_size: fields.crc32_expected as u64,
+            actual_size: fields.crc32_actual as u64,
+            error_type: "crc32 mismatch",
+        });
+    }
+    if fields.isize_expected as u64 != actual_size {
+        return Some(GzipValidationError {
+            claimed_size: fields.isize_expected as u64,
+            actual_size,
+            error_type: "isize mismatch",
+        });
+    }
+    None
+}
+
+/// Check a member's header for conditions RFC 1952 forbids: reserved FLG
+/// bits, and (when FLG.FHCRC is set) a header CRC16 that doesn't match.
+/// Folded into `integrity`/`validation` the same way trailer mismatches
+/// are, so `--verify` and `corrupted_members` count a non-conforming
+/// header as a real defect rather than a display-only note.
+pub(crate) fn header_validation_error(header_info: &GzipHeaderInfo) -> Option<GzipValidationError> {
+    if !header_info.reserved_flags_valid {
+        return Some(GzipValidationError {
+            claimed_size: 0,
+            actual_size: 0,
+            error_type: "reserved flags set",
+        });
+    }
+    if header_info.header_crc_ok == Some(false) {
+        return Some(GzipValidationError {
+            claimed_size: 0,
+            actual_size: 0,
+            error_type: "header crc16 mismatch",
+        });
+    }
+    None
+}
+
+/// Check a member's trailing 8-byte footer (CRC32 + ISIZE) against the
+/// actually decompressed bytes, returning the first mismatch found.
+pub fn validate_trailer(compressed_data: &[u8], decompressed: &[u8]) -> Result<(), GzipValidationError> {
+    if compressed_data.len() < GZIP_FOOTER_SIZE {
+        return Err(GzipValidationError {
+            claimed_size: 0,
+            actual_size: decompressed.len() as u64,
+            error_type: "missing trailer",
+        });
+    }
+    let footer_start = compressed_data.len() - GZIP_FOOTER_SIZE;
+    let fields = trailer_fields(&compressed_data[footer_start..], decompressed);
+    match trailer_validation_error(&fields, decompressed.len() as u64) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Check a member's trailing 8-byte footer (CRC32 + ISIZE) against the
+/// actually decompressed bytes. Returns `true` when both match.
+pub fn verify_member_trailer(compressed_data: &[u8], decompressed: &[u8]) -> bool {
+    validate_trailer(compressed_data, decompressed).is_ok()
+}
+
+/// The `(SI1, SI2)` subfield id BGZF (used by bgzipped BAM/VCF files)
+/// stores its `BSIZE` payload under, as packed by `parse_gzip_header`.
+const BGZF_SUBFIELD_ID: u16 = ((b'B' as u16) << 8) | b'C' as u16;
+
+/// The canonical 28-byte empty BGZF block that terminates a well-formed
+/// BGZF file.
+pub const BGZF_EOF_MARKER: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00,
+    0x42, 0x43, 0x02, 0x00, 0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00,
+];
+
+/// If `extra_fields` carries a BGZF `BC` subfield, return the member's
+/// total compressed block size (`BSIZE + 1`, per the BGZF convention).
+pub fn bgzf_block_size(extra_fields: &[(u16, Vec<u8>)]) -> Option<u32> {
+    extra_fields.iter().find_map(|(id, data)| {
+        if *id == BGZF_SUBFIELD_ID && data.len() == 2 {
+            let bsize = u16::from_le_bytes([data[0], data[1]]);
+            Some(bsize as u32 + 1)
+        } else {
+            None
+        }
+    })
+}
+
+pub fn is_bgzf_eof_marker(data: &[u8]) -> bool {
+    data == BGZF_EOF_MARKER
+}
+
+/// Write only the members whose `integrity` is not `Some(false)` (trailer,
+/// header and BGZF size checks, as set by `read_chunk`) to `writer`,
+/// re-emitting each intact member's raw compressed bytes unchanged. This
+/// lets a partially corrupted concatenated gzip file be salvaged by
+/// dropping the bad members, the same scan-report-drop workflow other
+/// chunked-storage tools use. Returns the number of members kept.
+pub fn repair_gzip_file<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    chunks: &[ChunkInfo],
+    writer: &mut W,
+) -> io::Result<usize> {
+    let mut kept = 0;
+    for chunk in chunks {
+        if chunk.integrity == Some(false) {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(chunk.offset))?;
+        let mut raw = vec![0u8; chunk.compressed_size as usize];
+        reader.read_exact(&mut raw)?;
+        writer.write_all(&raw)?;
+        kept += 1;
+    }
+    Ok(kept)
+}
+
+/// Upper bound on the NAME/COMMENT field length, matching flate2's own
+/// `MAX_HEADER_BUF` cap: without one, a crafted stream missing the NUL
+/// terminator would force an unbounded read/allocation.
+const MAX_HEADER_FIELD_LEN: usize = 65535;
+
+/// RFC 1952 requires the three high bits of FLG (0xE0) to be zero;
+/// real-world encoders never set them, so their presence means either a
+/// corrupt stream or a future flag extension this reader doesn't know
+/// about. Either way, the header shouldn't be trusted as-is.
+fn reserved_flags_set(flg: u8) -> bool {
+    flg & 0xE0 != 0
+}
+
+/// Read bytes up to and including a NUL terminator, returning the bytes
+/// before it. Stops (without erroring) on EOF, matching this reader's
+/// existing tolerance for a header cut short; only a field that runs past
+/// `MAX_HEADER_FIELD_LEN` without ever finding its terminator is an error.
+fn read_nul_terminated_field(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut field = Vec::new();
+    let mut buf = [0u8; 1];
+    while reader.read_exact(&mut buf).is_ok() && buf[0] != 0 {
+        field.push(buf[0]);
+        if field.len() > MAX_HEADER_FIELD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "gzip header field too long or missing NUL terminator",
+            ));
+        }
+    }
+    Ok(field)
+}
+
+/// Compute the 16-bit CRC gzip's optional FHCRC field stores: the low 16
+/// bits of the standard CRC32 over every header byte preceding it (the
+/// fixed 10-byte header plus any EXTRA/NAME/COMMENT fields).
+fn header_crc16(data: &[u8]) -> u16 {
+    (crc32(data) & 0xFFFF) as u16
+}
 
 pub fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<GzipHeaderInfo> {
     let mut flags = Vec::new();
@@ -12,6 +230,7 @@ pub fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<Gz
     if header[3] & 0x04 != 0 { flags.push("EXTRA".to_string()); }
     if header[3] & 0x08 != 0 { flags.push("NAME".to_string()); }
     if header[3] & 0x10 != 0 { flags.push("COMMENT".to_string()); }
+    let reserved_flags_valid = !reserved_flags_set(header[3]);
 
     let mtime = u32::from_le_bytes(header[4..8].try_into().unwrap());
     let mtime_str = if mtime == 0 {
@@ -50,14 +269,23 @@ pub fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<Gz
     let mut filename = None;
     let mut comment = None;
 
-    // Read extra fields if present
+    // Bytes consumed after the fixed 10-byte header, in header-CRC order:
+    // XLEN+extra, then the NUL-terminated name, then the NUL-terminated
+    // comment. RFC 1952's optional HCRC is computed over the whole header
+    // including these, so we mirror them here instead of re-reading them.
+    let mut crc_tail = Vec::new();
+
+    // Read extra fields if present. XLEN is itself a u16, so the EXTRA
+    // field is already implicitly bounded by MAX_HEADER_FIELD_LEN.
     if header[3] & 0x04 != 0 {
         let mut xlen_bytes = [0u8; 2];
         reader.read_exact(&mut xlen_bytes)?;
         let xlen = u16::from_le_bytes(xlen_bytes);
         let mut extra = vec![0u8; xlen as usize];
         reader.read_exact(&mut extra)?;
-        
+        crc_tail.extend_from_slice(&xlen_bytes);
+        crc_tail.extend_from_slice(&extra);
+
         let mut pos = 0;
         while pos + 4 <= extra.len() {
             let si1 = extra[pos];
@@ -75,24 +303,34 @@ pub fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<Gz
 
     // Read filename if present
     if header[3] & 0x08 != 0 {
-        let mut fname = Vec::new();
-        let mut buf = [0u8; 1];
-        while reader.read_exact(&mut buf).is_ok() && buf[0] != 0 {
-            fname.push(buf[0]);
-        }
+        let fname = read_nul_terminated_field(reader)?;
+        crc_tail.extend_from_slice(&fname);
+        crc_tail.push(0);
         filename = String::from_utf8(fname).ok();
     }
 
     // Read comment if present
     if header[3] & 0x10 != 0 {
-        let mut comment_bytes = Vec::new();
-        let mut buf = [0u8; 1];
-        while reader.read_exact(&mut buf).is_ok() && buf[0] != 0 {
-            comment_bytes.push(buf[0]);
-        }
+        let comment_bytes = read_nul_terminated_field(reader)?;
+        crc_tail.extend_from_slice(&comment_bytes);
+        crc_tail.push(0);
         comment = String::from_utf8(comment_bytes).ok();
     }
 
+    // Read and check the optional header CRC16 (FHCRC) last, since RFC 1952
+    // places it after EXTRA/NAME/COMMENT and it covers everything before it.
+    let (header_crc_expected, header_crc_actual) = if header[3] & 0x02 != 0 {
+        let mut crc_bytes = [0u8; 2];
+        reader.read_exact(&mut crc_bytes)?;
+        let stored = u16::from_le_bytes(crc_bytes);
+        let mut crc_input = header.to_vec();
+        crc_input.extend_from_slice(&crc_tail);
+        (Some(stored), Some(header_crc16(&crc_input)))
+    } else {
+        (None, None)
+    };
+    let header_crc_ok = header_crc_expected.map(|expected| Some(expected) == header_crc_actual);
+
     Ok(GzipHeaderInfo {
         compression_method: match header[2] {
             8 => "deflate".to_string(),
@@ -105,112 +343,336 @@ pub fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<Gz
         extra_fields,
         filename,
         comment,
+        header_crc_expected,
+        header_crc_actual,
+        header_crc_ok,
+        reserved_flags_valid,
     })
 }
 
+/// Dispatch to the gzip or zlib member reader, whichever the bytes at
+/// `offset` look like. This is the entry point `main.rs` drives in a loop
+/// over the whole file, so every `ChunkInfo` it returns (regardless of
+/// format) already carries a completed `compressed_size` to advance by.
+///
+/// Each member's boundary comes straight from `consume_deflate_member`'s
+/// `total_in` tracking, not from rescanning for the next `0x1f 0x8b` magic —
+/// there is no quadratic reclone-and-redecompress fallback anywhere in this
+/// path, including for the file's last member.
 pub fn read_chunk<R: Read + Seek>(reader: &mut R, offset: u64, chunk_number: usize) -> io::Result<ChunkInfo> {
     reader.seek(SeekFrom::Start(offset))?;
-    
-    // Read initial header
-    let mut header = [0u8; GZIP_HEADER_SIZE];
-    if reader.read_exact(&mut header).is_err() {
+    let mut magic = [0u8; 2];
+    if reader.read_exact(&mut magic).is_err() {
         return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "End of file"));
     }
 
-    // Validate GZIP magic numbers
-    if header[0] != 0x1f || header[1] != 0x8b {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, 
-            format!("Invalid GZIP header: {:02x} {:02x} {:02x}", header[0], header[1], header[2])));
+    if magic[0] == 0x1f && magic[1] == 0x8b {
+        read_gzip_chunk(reader, offset, chunk_number)
+    } else if is_zlib_magic(magic) {
+        read_zlib_chunk(reader, offset, chunk_number)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("Invalid GZIP/zlib header: {:02x} {:02x}", magic[0], magic[1])))
     }
+}
 
-    let header_info = parse_gzip_header(&header, reader)?;
-    
-    let mut compressed_data = Vec::with_capacity(8192);
-    compressed_data.extend_from_slice(&header);
-    
+/// Run a raw deflate stream forward from the reader's current position
+/// until the decoder reports `StreamEnd`, seeking back over whatever was
+/// over-read into the next member so the caller's position lands exactly
+/// on the trailer. Returns the number of compressed bytes consumed and the
+/// decompressed payload.
+///
+/// This replaces the old approach of cloning the accumulated compressed
+/// bytes and fully re-decompressing them on every candidate magic-byte
+/// match, which was quadratic on files with many members. Tracking
+/// `total_in`/`total_out` from a single `Decompress` instance means each
+/// input byte is only ever fed to the decoder once.
+pub(crate) fn consume_deflate_member<R: Read + Seek>(reader: &mut R) -> io::Result<(u64, Vec<u8>)> {
+    let mut decompress = Decompress::new(false);
+    let mut decompressed = Vec::new();
     let mut buffer = [0u8; 8192];
-    let mut found_next = false;
-    
-    'read_loop: loop {
+    let mut outbuf = [0u8; 8192];
+    let mut total_read = 0u64;
+
+    loop {
         let bytes_read = reader.read(&mut buffer)?;
         if bytes_read == 0 {
-            break;
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated deflate stream"));
         }
+        total_read += bytes_read as u64;
+        let mut input = &buffer[..bytes_read];
 
-        // Look for next GZIP header
-        for i in 0..bytes_read {
-            if bytes_read - i >= 2 && buffer[i] == 0x1f && i + 1 < bytes_read && buffer[i + 1] == 0x8b {
-                // Save current position
-                let current_pos = reader.stream_position()?;
-                
-                // Try to validate current chunk up to this point
-                let mut test_data = compressed_data.clone();
-                test_data.extend_from_slice(&buffer[..i]);
-                
-                let mut decoder = GzDecoder::new(&test_data[..]);
-                let mut test_buf = Vec::new();
-                
-                if decoder.read_to_end(&mut test_buf).is_ok() {
-                    // Valid chunk found
-                    compressed_data = test_data;
-                    reader.seek(SeekFrom::Start(offset + compressed_data.len() as u64))?;
-                    found_next = true;
-                    break 'read_loop;
+        while !input.is_empty() {
+            let before_in = decompress.total_in();
+            let before_out = decompress.total_out();
+            let status = decompress
+                .decompress(input, &mut outbuf, FlushDecompress::None)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            let consumed = (decompress.total_in() - before_in) as usize;
+            let produced = (decompress.total_out() - before_out) as usize;
+            decompressed.extend_from_slice(&outbuf[..produced]);
+            input = &input[consumed..];
+
+            if status == Status::StreamEnd {
+                if !input.is_empty() {
+                    reader.seek(SeekFrom::Current(-(input.len() as i64)))?;
                 }
-                
-                // If validation failed, restore position and continue
-                reader.seek(SeekFrom::Start(current_pos))?;
+                let total_consumed = total_read - input.len() as u64;
+                return Ok((total_consumed, decompressed));
             }
-        }
 
-        compressed_data.extend_from_slice(&buffer[..bytes_read]);
-        
-        // Safety limit with a more generous size for last chunk
-        if compressed_data.len() > 20 * 1024 * 1024 {
-            // Try to decompress what we have so far
-            let mut decoder = GzDecoder::new(&compressed_data[..]);
-            let mut test_buf = Vec::new();
-            if decoder.read_to_end(&mut test_buf).is_ok() {
+            if consumed == 0 && produced == 0 {
+                // Decoder needs a fresh read; this slice is exhausted.
                 break;
             }
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk too large"));
         }
     }
+}
 
-    // Handle last chunk
-    if !found_next {
-        // Try to decompress full chunk first
-        let mut decoder = GzDecoder::new(&compressed_data[..]);
-        let mut test_buf = Vec::new();
-        if decoder.read_to_end(&mut test_buf).is_err() {
-            // If full decompression fails, try to find a valid ending
-            for i in (GZIP_HEADER_SIZE..compressed_data.len()).rev() {
-                let test_slice = &compressed_data[..i];
-                let mut decoder = GzDecoder::new(test_slice);
-                let mut test_buf = Vec::new();
-                if decoder.read_to_end(&mut test_buf).is_ok() {
-                    compressed_data.truncate(i);
-                    break;
-                }
+fn read_gzip_chunk<R: Read + Seek>(reader: &mut R, offset: u64, chunk_number: usize) -> io::Result<ChunkInfo> {
+    reader.seek(SeekFrom::Start(offset))?;
+
+    // Read initial header
+    let mut header = [0u8; GZIP_HEADER_SIZE];
+    if reader.read_exact(&mut header).is_err() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "End of file"));
+    }
+
+    // Validate GZIP magic numbers
+    if header[0] != 0x1f || header[1] != 0x8b {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("Invalid GZIP header: {:02x} {:02x} {:02x}", header[0], header[1], header[2])));
+    }
+
+    let header_info = parse_gzip_header(&header, reader)?;
+
+    let (_, decompressed) = consume_deflate_member(reader)?;
+
+    let mut footer = [0u8; GZIP_FOOTER_SIZE];
+    reader.read_exact(&mut footer)?;
+
+    let compressed_size = reader.stream_position()? - offset;
+
+    let trailer = trailer_fields(&footer, &decompressed);
+    let mut validation = header_validation_error(&header_info)
+        .or_else(|| trailer_validation_error(&trailer, decompressed.len() as u64));
+    let bgzf_block_size = bgzf_block_size(&header_info.extra_fields);
+    if let Some(bsize) = bgzf_block_size {
+        if validation.is_none() && bsize as u64 != compressed_size {
+            validation = Some(GzipValidationError {
+                claimed_size: bsize as u64,
+                actual_size: compressed_size,
+                error_type: "bgzf size mismatch",
+            });
+        }
+    }
+    let integrity = Some(validation.is_none());
+    let bgzf_virtual_offset = bgzf_block_size.map(|_| bgzf_virtual_offset(offset, 0));
+
+    Ok(ChunkInfo {
+        chunk_number,
+        offset,
+        compressed_size,
+        uncompressed_size: decompressed.len() as u64,
+        compression_ratio: decompressed.len() as f64 / compressed_size as f64,
+        header_info: header_info.to_string(),
+        integrity,
+        validation,
+        bgzf_block_size,
+        bgzf_virtual_offset,
+        format: Format::Gzip,
+        window_size: None,
+        compression_level: None,
+        dictionary_id: None,
+        crc32_expected: Some(trailer.crc32_expected),
+        crc32_actual: Some(trailer.crc32_actual),
+        isize_expected: Some(trailer.isize_expected),
+        preview: None,
+        preview_data: Some(decompressed),
+    })
+}
+
+/// Walk the whole file once, recording each member's start offset. Used to
+/// hand out independent per-member work to `--jobs` worker threads: the
+/// boundaries require one sequential decode pass (a member's start can
+/// only be known once its predecessor has been decoded), which is linear
+/// in file size rather than quadratic. Each member is decoded with
+/// `read_chunk`, so this pass does the same full inflate and trailer check
+/// that the parallel per-member decode then repeats.
+pub fn scan_member_offsets<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<u64>> {
+    let mut offsets = Vec::new();
+    let mut offset = 0u64;
+    let mut chunk_number = 0;
+    loop {
+        match read_chunk(reader, offset, chunk_number) {
+            Ok(info) => {
+                offset += info.compressed_size;
+                offsets.push(info.offset);
+                chunk_number += 1;
             }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
         }
     }
+    Ok(offsets)
+}
 
-    // Final decompression attempt
-    let mut decoder = GzDecoder::new(&compressed_data[..]);
-    let mut decompressed = Vec::new();
-    
-    match decoder.read_to_end(&mut decompressed) {
-        Ok(size) => Ok(ChunkInfo {
-            chunk_number,
-            offset,
-            compressed_size: compressed_data.len() as u64,
-            uncompressed_size: size as u64,
-            compression_ratio: size as f64 / compressed_data.len() as f64,
-            header_info: header_info.to_string(),
-            preview_data: Some(decompressed),
-        }),
-        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, 
-            format!("Decompression error at offset {}: {}", offset, e)))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_standard_check_value() {
+        // The "123456789" check value from the CRC-32/ISO-HDLC catalog
+        // entry, which is also the algorithm gzip trailers use.
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn crc32_of_empty_input_is_zero() {
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn header_crc16_is_low_16_bits_of_crc32() {
+        assert_eq!(header_crc16(b"123456789"), 0x3926);
+    }
+
+    #[test]
+    fn header_crc16_differs_for_different_input() {
+        assert_ne!(header_crc16(b"abc"), header_crc16(b"abd"));
+    }
+
+    fn make_gzip_member(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn read_chunk_walks_concatenated_members_and_verifies_trailers() {
+        let first = make_gzip_member(b"hello world");
+        let second = make_gzip_member(b"a second, somewhat longer member");
+        let mut combined = first.clone();
+        combined.extend_from_slice(&second);
+        let mut reader = io::Cursor::new(combined);
+
+        let chunk0 = read_chunk(&mut reader, 0, 0).unwrap();
+        assert_eq!(chunk0.compressed_size, first.len() as u64);
+        assert_eq!(chunk0.uncompressed_size, 11);
+        assert_eq!(chunk0.integrity, Some(true));
+
+        let chunk1 = read_chunk(&mut reader, chunk0.compressed_size, 1).unwrap();
+        assert_eq!(chunk1.uncompressed_size, b"a second, somewhat longer member".len() as u64);
+        assert_eq!(chunk1.integrity, Some(true));
+    }
+
+    #[test]
+    fn read_chunk_flags_crc32_mismatch() {
+        let mut member = make_gzip_member(b"corrupt me");
+        let crc32_start = member.len() - GZIP_FOOTER_SIZE;
+        member[crc32_start] ^= 0xff;
+        let mut reader = io::Cursor::new(member);
+
+        let chunk = read_chunk(&mut reader, 0, 0).unwrap();
+        assert_eq!(chunk.integrity, Some(false));
+        assert_eq!(chunk.validation.unwrap().error_type, "crc32 mismatch");
+    }
+
+    #[test]
+    fn read_chunk_flags_isize_mismatch() {
+        let mut member = make_gzip_member(b"corrupt me");
+        let isize_start = member.len() - 4;
+        member[isize_start] ^= 0xff;
+        let mut reader = io::Cursor::new(member);
+
+        let chunk = read_chunk(&mut reader, 0, 0).unwrap();
+        assert_eq!(chunk.integrity, Some(false));
+        assert_eq!(chunk.validation.unwrap().error_type, "isize mismatch");
+    }
+
+    fn make_gzip_member_with_fhcrc(data: &[u8], corrupt_crc: bool) -> Vec<u8> {
+        let deflated = {
+            let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let header = vec![0x1f, 0x8b, 0x08, 0x02, 0, 0, 0, 0, 0, 0xff];
+        let mut header_crc = header_crc16(&header);
+        if corrupt_crc {
+            header_crc ^= 0xffff;
+        }
+
+        let mut member = header;
+        member.extend_from_slice(&header_crc.to_le_bytes());
+        member.extend_from_slice(&deflated);
+        member.extend_from_slice(&crc32(data).to_le_bytes());
+        member.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        member
+    }
+
+    #[test]
+    fn read_chunk_flags_header_crc16_mismatch() {
+        let member = make_gzip_member_with_fhcrc(b"bad header crc", true);
+        let mut reader = io::Cursor::new(member);
+
+        let chunk = read_chunk(&mut reader, 0, 0).unwrap();
+        assert_eq!(chunk.integrity, Some(false));
+        assert_eq!(chunk.validation.unwrap().error_type, "header crc16 mismatch");
+    }
+
+    #[test]
+    fn read_chunk_accepts_matching_header_crc16() {
+        let member = make_gzip_member_with_fhcrc(b"good header crc", false);
+        let mut reader = io::Cursor::new(member);
+
+        let chunk = read_chunk(&mut reader, 0, 0).unwrap();
+        assert_eq!(chunk.integrity, Some(true));
+    }
+
+    #[test]
+    fn consume_deflate_member_leaves_reader_at_stream_end() {
+        let deflated = {
+            let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(b"raw deflate payload").unwrap();
+            encoder.finish().unwrap()
+        };
+        let mut data = deflated.clone();
+        // Stand-in trailer bytes after the deflate stream. consume_deflate_member
+        // reads them into its buffer along with the stream, so they are only
+        // where the reader lands next if it seeks back over exactly those
+        // over-read bytes and never feeds them to the decoder.
+        data.extend_from_slice(b"TRAILER!");
+        let mut reader = io::Cursor::new(data);
+
+        let (consumed, decompressed) = consume_deflate_member(&mut reader).unwrap();
+
+        assert_eq!(decompressed, b"raw deflate payload");
+        assert_eq!(consumed, deflated.len() as u64);
+        let mut trailer = [0u8; 8];
+        reader.read_exact(&mut trailer).unwrap();
+        assert_eq!(&trailer, b"TRAILER!");
+    }
+
+    #[test]
+    fn repair_gzip_file_drops_only_the_corrupted_member() {
+        let good = make_gzip_member(b"keep me");
+        let mut bad = make_gzip_member(b"drop me");
+        let crc32_start = bad.len() - GZIP_FOOTER_SIZE;
+        bad[crc32_start] ^= 0xff;
+        let mut combined = good.clone();
+        combined.extend_from_slice(&bad);
+        let mut reader = io::Cursor::new(combined);
+
+        let chunk0 = read_chunk(&mut reader, 0, 0).unwrap();
+        let chunk1 = read_chunk(&mut reader, chunk0.compressed_size, 1).unwrap();
+        assert_eq!(chunk0.integrity, Some(true));
+        assert_eq!(chunk1.integrity, Some(false));
+
+        let mut repaired = Vec::new();
+        let kept = repair_gzip_file(&mut reader, &[chunk0, chunk1], &mut repaired).unwrap();
+
+        assert_eq!(kept, 1);
+        assert_eq!(repaired, good);
     }
 }
\ No newline at end of file
@@ -1,8 +1,18 @@
 mod models;
 mod gzip;
+mod zlib;
+mod stream;
 mod printer;
 mod utils;
+mod chunker;
+mod index;
+mod record;
 
 pub use models::*;
 pub use gzip::*;
-pub use printer::*;
\ No newline at end of file
+pub use zlib::*;
+pub use stream::*;
+pub use printer::*;
+pub use chunker::*;
+pub use index::*;
+pub use record::*;
\ No newline at end of file
@@ -1,254 +1,327 @@
 use clap::{value_parser, Arg, Command};
-use flate2::read::GzDecoder;
-use serde::Serialize;
+use gzinspector::*;
+use std::collections::HashMap;
 use std::fs::File;
-use std::convert::TryInto;
-use std::io::{self, BufReader, Read, Seek, SeekFrom};
-use std::fmt;
-use chrono::DateTime;
+use std::io::{self, BufReader, BufWriter, Read, Seek};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use indicatif::{ProgressBar, ProgressStyle};
 
-#[derive(Serialize, Debug, Clone)]
-struct ChunkInfo {
-    chunk_number: usize,
-    offset: u64,
-    compressed_size: u64,
-    uncompressed_size: u64,
-    compression_ratio: f64,
-    header_info: String,
-    #[serde(skip)]
-    preview_data: Option<Vec<u8>>,
-}
+fn main() {
+    let matches = Command::new("gz_inspector")
+        .version("1.0")
+        .author("Jason Grey <jason@jason-grey.com>")
+        .about("Inspect gzip/zlib compressed files")
+        .arg(Arg::new("file")
+            .help("The gzip/zlib file to inspect, or '-' to read from stdin")
+            .required(true)
+            .index(1))
+        .arg(Arg::new("output_format")
+            .short('o')
+            .long("output-format")
+            .help("Output format: human or json")
+            .value_parser(["human", "json"])
+            .default_value("human"))
+        .arg(Arg::new("preview")
+            .short('p')
+            .long("preview")
+            .help("Preview content (format: HEAD:TAIL, e.g. '5:3' shows first 5 and last 3 lines)")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("encoding")
+            .short('e')
+            .long("encoding")
+            .help("Encoding for preview: utf-8, latin1, utf-16le, utf-16be, or hex (xxd-style dump)")
+            .value_parser(["utf-8", "latin1", "utf-16le", "utf-16be", "hex"])
+            .default_value("utf-8"))
+        .arg(Arg::new("chunks")
+            .short('c')
+            .long("chunks")
+            .help("Filter chunks to display (format: HEAD:TAIL, e.g. '5:3' shows first 5 and last 3 chunks)")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("dedup")
+            .short('d')
+            .long("dedup")
+            .help("Analyze dedup potential of the decompressed content (format: AVG_KIB[:ALGORITHM], e.g. '64' or '64:fastcdc')")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("repair")
+            .long("repair")
+            .help("Write a repaired copy of the file containing only members that pass integrity verification")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("write_index")
+            .long("write-index")
+            .help("Write a seekable .gzi-style block index to the given path")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("extract")
+            .long("extract")
+            .help("Extract an uncompressed byte range (format: START:LEN) using the block index, writing raw bytes to stdout")
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("verify")
+            .long("verify")
+            .help("Exit with a non-zero status if any member fails integrity verification (trailer checksum/size, header CRC16 or reserved flags, BGZF block size)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("jobs")
+            .short('j')
+            .long("jobs")
+            .help("Decode members across N worker threads once boundaries are indexed (requires a seekable file)")
+            .value_parser(value_parser!(usize)))
+        .get_matches();
 
-#[derive(Debug, Serialize)]
-struct GzipHeaderInfo {
-    compression_method: String,
-    flags: Vec<String>,
-    mtime: String,
-    extra_flags: String,
-    os: String,
-    extra_fields: Vec<(u16, Vec<u8>)>,
-    filename: Option<String>,
-    comment: Option<String>,
-}
+    let file_path = matches.get_one::<String>("file").unwrap().clone();
+    let options = InspectOptions {
+        output_format: matches.get_one::<String>("output_format").unwrap().clone(),
+        preview: matches.get_one::<String>("preview").cloned(),
+        encoding: matches.get_one::<String>("encoding").unwrap().clone(),
+        chunks: matches.get_one::<String>("chunks").cloned(),
+        dedup: matches.get_one::<String>("dedup").cloned(),
+        repair: matches.get_one::<String>("repair").cloned(),
+        write_index: matches.get_one::<String>("write_index").cloned(),
+        extract: matches.get_one::<String>("extract").cloned(),
+        verify: matches.get_flag("verify"),
+        jobs: matches.get_one::<usize>("jobs").copied(),
+    };
+    let verify = options.verify;
 
-impl fmt::Display for GzipHeaderInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}|{}", 
-            self.compression_method,
-            self.flags.join("|"))?;
-        if let Some(fname) = &self.filename {
-            write!(f, "|{}", fname)?;
+    let result = if file_path == "-" {
+        inspect_stream(&options)
+    } else if let Some(jobs) = options.jobs.filter(|&n| n > 1) {
+        inspect_file_parallel(&file_path, &options, jobs)
+    } else {
+        inspect_file(&file_path, &options)
+    };
+
+    match result {
+        Ok(corrupted_members) => {
+            if verify && corrupted_members > 0 {
+                eprintln!("Error: {} member(s) failed integrity verification", corrupted_members);
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
-        Ok(())
     }
 }
 
-impl fmt::Display for ChunkInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let ratio = if self.compression_ratio >= 1.0 {
-            format!("🔓 {:.1}x", self.compression_ratio)
-        } else {
-            format!("🔒 {:.1}x", 1.0 / self.compression_ratio)
-        };
-
-        write!(f, "📦 #{:<5} │ 📍 {:<10} │ {} │ 📥 {:<8} │ 📤 {:<8} │ ℹ️  {}",
-            self.chunk_number,
-            self.offset,
-            ratio,
-            human_size(self.compressed_size),
-            human_size(self.uncompressed_size),
-            self.header_info)
-    }
+/// CLI-level knobs for a single `inspect_file` run, gathered from clap
+/// matches once so the growing list of optional modes doesn't turn into a
+/// long positional parameter list.
+struct InspectOptions {
+    output_format: String,
+    preview: Option<String>,
+    encoding: String,
+    chunks: Option<String>,
+    dedup: Option<String>,
+    repair: Option<String>,
+    write_index: Option<String>,
+    extract: Option<String>,
+    verify: bool,
+    jobs: Option<usize>,
 }
 
-#[derive(Serialize, Debug)]
-struct FileSummary {
-    total_chunks: usize,
+/// Running totals accumulated as each member is read, shared between the
+/// sequential and parallel scan loops so the two can't drift apart.
+#[derive(Default)]
+struct ScanState {
     total_compressed_size: u64,
     total_uncompressed_size: u64,
-    average_compression_ratio: f64,
+    corrupted_members: usize,
+    zlib_members: usize,
+    saw_bgzf: bool,
 }
 
-impl fmt::Display for FileSummary {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "\n📊 Summary:\n")?;
-        write!(f, "├─ 📦 Chunks: {}\n", self.total_chunks)?;
-        write!(f, "├─ 📥 Total Compressed: {}\n", human_size(self.total_compressed_size))?;
-        write!(f, "├─ 📤 Total Uncompressed: {}\n", human_size(self.total_uncompressed_size))?;
-        write!(f, "└─ 📈 Average Compression: {:.1}x", self.average_compression_ratio)
+impl ScanState {
+    fn record(&mut self, chunk_info: &ChunkInfo) {
+        if chunk_info.integrity == Some(false) {
+            self.corrupted_members += 1;
+        }
+        if chunk_info.format == Format::Zlib {
+            self.zlib_members += 1;
+        }
+        if chunk_info.bgzf_block_size.is_some() {
+            self.saw_bgzf = true;
+        }
+        self.total_compressed_size += chunk_info.compressed_size;
+        self.total_uncompressed_size += chunk_info.uncompressed_size;
+    }
+
+    fn summary(&self, total_chunks: usize) -> FileSummary {
+        FileSummary {
+            total_chunks,
+            total_compressed_size: self.total_compressed_size,
+            total_uncompressed_size: self.total_uncompressed_size,
+            average_compression_ratio: self.total_uncompressed_size as f64
+                / self.total_compressed_size as f64,
+            corrupted_members: self.corrupted_members,
+            zlib_members: self.zlib_members,
+        }
     }
 }
 
-fn human_size(size: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size = size as f64;
-    let mut unit_index = 0;
-
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-
-    if unit_index == 0 {
-        format!("{:.0}{}", size, UNITS[unit_index])
-    } else {
-        format!("{:.1}{}", size, UNITS[unit_index])
+/// Folds a freshly read member into the dedup buffer and running totals,
+/// then renders its preview if requested. Shared between the sequential
+/// and parallel scan loops, which otherwise read this block identically.
+fn finish_chunk(
+    chunk_info: &mut ChunkInfo,
+    preview_settings: &Option<PreviewSettings>,
+    encoding: &str,
+    dedup_buffer: &mut Option<Vec<u8>>,
+    state: &mut ScanState,
+) {
+    if let (Some(buf), Some(data)) = (dedup_buffer.as_mut(), chunk_info.preview_data.as_ref()) {
+        buf.extend_from_slice(data);
     }
-}
 
-struct PreviewSettings {
-    head_lines: usize,
-    tail_lines: Option<usize>,
-}
+    state.record(chunk_info);
 
-impl PreviewSettings {
-    fn parse(preview_arg: Option<&str>) -> Option<Self> {
-        preview_arg.map(|p| {
-            let parts: Vec<&str> = p.split(':').collect();
-            let head = parts[0].parse().unwrap_or(5);
-            let tail = parts.get(1).and_then(|s| s.parse().ok());
-            PreviewSettings {
-                head_lines: head,
-                tail_lines: tail,
-            }
-        })
+    if let Some(settings) = preview_settings {
+        if let Some(data) = &chunk_info.preview_data {
+            chunk_info.preview = Some(render_preview(data, settings, encoding));
+        }
     }
 }
 
-struct ChunkFilterSettings {
-    head_chunks: usize,
-    tail_chunks: Option<usize>,
-}
-
-impl ChunkFilterSettings {
-    fn parse(filter_arg: Option<&str>) -> Option<Self> {
-        filter_arg.map(|p| {
-            let parts: Vec<&str> = p.split(':').collect();
-            let head = parts[0].parse().unwrap_or(5);
-            let tail = parts.get(1).and_then(|s| s.parse().ok());
-            ChunkFilterSettings {
-                head_chunks: head,
-                tail_chunks: tail,
+/// Decides whether a member should print immediately under `--chunks
+/// head:tail` filtering, buffering it into `tail_buffer` instead when it
+/// falls in the tail window but hasn't surfaced yet. Shared by every scan
+/// path so `-j` can't print members in a different order than the default
+/// path for the same `-c` argument.
+fn should_print_chunk(
+    chunk_filter: Option<&ChunkFilterSettings>,
+    tail_buffer: &mut Option<TailBuffer>,
+    chunk_number: usize,
+    chunk_info: &ChunkInfo,
+) -> bool {
+    chunk_filter
+        .map(|f| {
+            if chunk_number < f.head_chunks {
+                true
+            } else if let Some(buffer) = tail_buffer.as_mut() {
+                if buffer.should_buffer(chunk_number) {
+                    buffer.add(chunk_info.clone());
+                    false
+                } else {
+                    false
+                }
+            } else {
+                true
             }
         })
-    }
+        .unwrap_or(true)
+}
 
-    fn should_print_chunk(&self, chunk_num: usize, total_chunks: usize) -> bool {
-        if chunk_num < self.head_chunks {
-            return true;
-        }
-        if let Some(tail) = self.tail_chunks {
-            if chunk_num >= total_chunks.saturating_sub(tail) {
-                return true;
-            }
+/// Prints one member's report line (plus preview, if any) in the selected
+/// output format.
+fn print_chunk_info(chunk_info: &ChunkInfo, output_format: &str) -> io::Result<()> {
+    if output_format == "json" {
+        print!("{}", serde_json::to_string(chunk_info)?);
+        println!();
+    } else {
+        println!("{}", chunk_info);
+        if let Some(preview) = &chunk_info.preview {
+            print_preview(preview);
         }
-        false
     }
+    Ok(())
 }
 
-struct TailBuffer {
-    chunks: Vec<ChunkInfo>,
-    capacity: usize,
-    total_seen: usize,
+/// Prints the file-level summary in the selected output format.
+fn print_summary(summary: &FileSummary, output_format: &str) -> io::Result<()> {
+    if output_format == "json" {
+        println!("{}", serde_json::to_string(summary)?);
+    } else {
+        println!("{}", summary);
+    }
+    Ok(())
 }
 
-impl TailBuffer {
-    fn new(capacity: usize) -> Self {
-        Self {
-            chunks: Vec::with_capacity(capacity),
-            capacity,
-            total_seen: 0,
+/// Prints whether the file ends with the canonical 28-byte empty BGZF EOF
+/// block; its absence usually means the file was truncated mid-transfer.
+/// Requires seeking the underlying file, so only the two file-backed scan
+/// paths call this (a stdin pipe can't seek).
+fn print_bgzf_eof_status(file_path: &str, file_size: u64) -> io::Result<()> {
+    if file_size >= BGZF_EOF_MARKER.len() as u64 {
+        let mut tail = vec![0u8; BGZF_EOF_MARKER.len()];
+        let mut source = File::open(file_path)?;
+        source.seek(io::SeekFrom::End(-(BGZF_EOF_MARKER.len() as i64)))?;
+        source.read_exact(&mut tail)?;
+        if is_bgzf_eof_marker(&tail) {
+            println!("🧬 BGZF EOF marker present");
+        } else {
+            println!("🧬 BGZF EOF marker missing (file may be truncated)");
         }
     }
+    Ok(())
+}
 
-    fn add(&mut self, chunk: ChunkInfo) {
-        self.total_seen += 1;
-        if self.chunks.len() < self.capacity {
-            self.chunks.push(chunk);
-        } else {
-            let idx = self.total_seen % self.capacity;
-            if let Some(slot) = self.chunks.get_mut(idx) {
-                *slot = chunk;
-            }
+/// Prints the dedup analysis report, if `--dedup` was requested.
+fn print_dedup_report(
+    chunker_settings: Option<&ChunkerSettings>,
+    dedup_buffer: Option<&Vec<u8>>,
+    output_format: &str,
+) {
+    if let (Some(settings), Some(data)) = (chunker_settings, dedup_buffer) {
+        let report = analyze(data, settings);
+        if output_format == "human" {
+            println!("{}", report);
         }
     }
+}
 
-    fn should_buffer(&self, chunk_num: usize) -> bool {
-        chunk_num >= self.total_seen.saturating_sub(self.capacity)
+/// Writes the `--repair`, `--write-index`, and `--extract` outputs, all of
+/// which depend on the fully decoded member list; `--repair` and
+/// `--extract` also re-read raw bytes from `file_path` by offset. Shared between the sequential and parallel
+/// scan paths once they've both assembled `all_chunks`.
+fn write_post_scan_outputs(
+    file_path: &str,
+    options: &InspectOptions,
+    all_chunks: &[ChunkInfo],
+) -> io::Result<()> {
+    if let Some(output_path) = options.repair.as_deref() {
+        let source = File::open(file_path)?;
+        let mut source_reader = BufReader::new(source);
+        let output = File::create(output_path)?;
+        let mut output_writer = BufWriter::new(output);
+        let kept = repair_gzip_file(&mut source_reader, all_chunks, &mut output_writer)?;
+        eprintln!("Repaired file written to {} ({} of {} members kept)", output_path, kept, all_chunks.len());
     }
 
-    fn get_buffered(&self) -> Vec<&ChunkInfo> {
-        if self.total_seen <= self.capacity {
-            self.chunks.iter().collect()
-        } else {
-            let start_idx = self.total_seen % self.capacity;
-            let mut result = Vec::with_capacity(self.capacity);
-            // First add the chunks from start_idx to end (older chunks)
-            result.extend(&self.chunks[start_idx..]);
-            // Then add the chunks from beginning to start_idx (newer chunks)
-            result.extend(&self.chunks[..start_idx]);
-            result
-        }
+    if let Some(index_path) = options.write_index.as_deref() {
+        let entries = build_index(all_chunks);
+        let output = File::create(index_path)?;
+        let mut output_writer = BufWriter::new(output);
+        write_gzi_index(&entries, &mut output_writer)?;
+        eprintln!("Wrote block index to {} ({} entries)", index_path, entries.len());
     }
-}
 
-fn main() {
-    let matches = Command::new("gz_inspector")
-        .version("1.0")
-        .author("Jason Grey <jason@jason-grey.com>")
-        .about("Inspect gzip/zlib compressed files")
-        .arg(Arg::new("file")
-            .help("The gzip/zlib file to inspect")
-            .required(true)
-            .index(1))
-        .arg(Arg::new("output_format")
-            .short('o')
-            .long("output-format")
-            .help("Output format: human or json")
-            .value_parser(["human", "json"])
-            .default_value("human"))
-        .arg(Arg::new("preview")
-            .short('p')
-            .long("preview")
-            .help("Preview content (format: HEAD:TAIL, e.g. '5:3' shows first 5 and last 3 lines)")
-            .value_parser(value_parser!(String)))
-        .arg(Arg::new("encoding")
-            .short('e')
-            .long("encoding")
-            .help("Encoding for preview (default: utf-8)")
-            .value_parser(value_parser!(String))
-            .default_value("utf-8"))
-        .arg(Arg::new("chunks")
-            .short('c')
-            .long("chunks")
-            .help("Filter chunks to display (format: HEAD:TAIL, e.g. '5:3' shows first 5 and last 3 chunks)")
-            .value_parser(value_parser!(String)))
-        .get_matches();
+    if let Some(range) = options.extract.as_deref() {
+        let parts: Vec<&str> = range.split(':').collect();
+        let start: u64 = parts.first().and_then(|s| s.parse().ok()).unwrap_or(0);
+        let length: u64 = parts.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
 
-    let file_path = matches.get_one::<String>("file").unwrap();
-    let output_format = matches.get_one::<String>("output_format").unwrap();
-    let preview = matches.get_one::<String>("preview");
-    let encoding = matches.get_one::<String>("encoding").unwrap();
-    let chunks = matches.get_one::<String>("chunks");
+        let entries = build_index(all_chunks);
+        let source = File::open(file_path)?;
+        let mut source_reader = BufReader::new(source);
+        let bytes = extract_range(&mut source_reader, &entries, start, length)?;
 
-    match inspect_file(file_path, output_format, preview.map(|s| s.as_str()), encoding, chunks.map(|s| s.as_str())) {
-        Ok(_) => (),
-        Err(e) => eprintln!("Error: {}", e),
+        use std::io::Write as _;
+        io::stdout().write_all(&bytes)?;
     }
+
+    Ok(())
 }
 
-fn inspect_file(
-    file_path: &str, 
-    output_format: &str, 
-    preview: Option<&str>, 
-    encoding: &str,
-    chunks: Option<&str>
-) -> io::Result<()> {
+fn inspect_file(file_path: &str, options: &InspectOptions) -> io::Result<usize> {
+    let output_format = options.output_format.as_str();
+    let preview = options.preview.as_deref();
+    let encoding = options.encoding.as_str();
+    let chunks = options.chunks.as_deref();
+    let dedup = options.dedup.as_deref();
+    let needs_all_chunks = options.repair.is_some() || options.write_index.is_some() || options.extract.is_some();
     let file = File::open(file_path)?;
     let file_size = file.metadata()?.len();
     let mut reader = BufReader::new(file);
-    
+
     // Create progress bar on stderr
     let progress = ProgressBar::new(file_size).with_style(
         ProgressStyle::default_bar()
@@ -260,10 +333,15 @@ fn inspect_file(
 
     let mut offset = 0;
     let mut chunk_number = 0;
-    let mut total_compressed_size = 0;
-    let mut total_uncompressed_size = 0;
+    let mut state = ScanState::default();
     let preview_settings = PreviewSettings::parse(preview);
     let chunk_filter = ChunkFilterSettings::parse(chunks);
+    let chunker_settings = ChunkerSettings::parse(dedup);
+    let mut dedup_buffer = chunker_settings.as_ref().map(|_| Vec::new());
+    let mut all_chunks = needs_all_chunks.then(Vec::new);
+    // `--extract` writes raw extracted bytes to stdout, so none of the
+    // per-member/summary/report output below can share that stream.
+    let extract_active = options.extract.is_some();
 
     // Initialize tail buffer if needed
     let mut tail_buffer = chunk_filter.as_ref()
@@ -271,7 +349,7 @@ fn inspect_file(
         .map(|tail| TailBuffer::new(tail));
 
     loop {
-        let chunk_info = match read_chunk(&mut reader, offset, chunk_number) {
+        let mut chunk_info = match read_chunk(&mut reader, offset, chunk_number) {
             Ok(info) => info,
             Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
             Err(e) => {
@@ -283,39 +361,18 @@ fn inspect_file(
         // Update progress
         progress.set_position(offset);
 
-        let should_print = chunk_filter.as_ref()
-            .map(|f| {
-                if chunk_number < f.head_chunks {
-                    true
-                } else if let Some(ref mut buffer) = tail_buffer {
-                    if buffer.should_buffer(chunk_number) {
-                        buffer.add(chunk_info.clone());
-                        false
-                    } else {
-                        false
-                    }
-                } else {
-                    true
-                }
-            })
-            .unwrap_or(true);
+        finish_chunk(&mut chunk_info, &preview_settings, encoding, &mut dedup_buffer, &mut state);
 
-        if should_print {
-            if output_format == "json" {
-                print!("{}", serde_json::to_string(&chunk_info)?);
-                println!();
-            } else {
-                println!("{}", chunk_info);
-                if let Some(settings) = &preview_settings {
-                    if let Some(data) = &chunk_info.preview_data {
-                        print_preview(data, settings, encoding);
-                    }
-                }
-            }
+        if let Some(keep) = all_chunks.as_mut() {
+            keep.push(chunk_info.clone());
+        }
+
+        let should_print = should_print_chunk(chunk_filter.as_ref(), &mut tail_buffer, chunk_number, &chunk_info);
+
+        if should_print && !extract_active {
+            print_chunk_info(&chunk_info, output_format)?;
         }
 
-        total_compressed_size += chunk_info.compressed_size;
-        total_uncompressed_size += chunk_info.uncompressed_size;
         offset += chunk_info.compressed_size;
         chunk_number += 1;
     }
@@ -325,372 +382,358 @@ fn inspect_file(
 
     // Print buffered tail chunks
     if let Some(buffer) = tail_buffer {
-        if chunk_number > buffer.capacity {
-            if output_format == "human" {
+        if !extract_active {
+            if chunk_number > buffer.capacity && output_format == "human" {
                 println!("          ...");
             }
-        }
-        for chunk in buffer.get_buffered() {
-            if output_format == "json" {
-                print!("{}", serde_json::to_string(chunk)?);
-                println!();
-            } else {
-                println!("{}", chunk);
-                if let Some(settings) = &preview_settings {
-                    if let Some(data) = &chunk.preview_data {
-                        print_preview(data, settings, encoding);
-                    }
-                }
+            for chunk in buffer.get_buffered() {
+                print_chunk_info(chunk, output_format)?;
             }
         }
     }
 
-    // Print summary
-    let summary = FileSummary {
-        total_chunks: chunk_number,
-        total_compressed_size,
-        total_uncompressed_size,
-        average_compression_ratio: total_uncompressed_size as f64 / total_compressed_size as f64,
-    };
+    if !extract_active {
+        print_summary(&state.summary(chunk_number), output_format)?;
 
-    if output_format == "json" {
-        println!("{}", serde_json::to_string(&summary)?);
-    } else {
-        println!("{}", summary);
-    }
-
-    Ok(())
-}
+        // BGZF files should end with a canonical 28-byte empty EOF block;
+        // its absence usually means the file was truncated mid-transfer.
+        if state.saw_bgzf && output_format == "human" {
+            print_bgzf_eof_status(file_path, file_size)?;
+        }
 
-fn print_preview(data: &[u8], settings: &PreviewSettings, encoding: &str) {
-    let text = String::from_utf8_lossy(data).into_owned();
-    let lines: Vec<&str> = text.lines().collect();
-    
-    // Print head lines
-    let head = settings.head_lines.min(lines.len());
-    for (i, line) in lines[..head].iter().enumerate() {
-        println!("     {:>4} │ {}", i + 1, line);
+        print_dedup_report(chunker_settings.as_ref(), dedup_buffer.as_ref(), output_format);
     }
-    
-    // Print tail lines if requested
-    if let Some(tail_count) = settings.tail_lines {
-        if head < lines.len() {
-            println!("          | ...");
-            let start = lines.len().saturating_sub(tail_count);
-            for (i, line) in lines[start..].iter().enumerate() {
-                println!("     {:>4} │ {}", start + i + 1, line);
-            }
-        }
+
+    if let Some(all) = all_chunks.as_ref() {
+        write_post_scan_outputs(file_path, options, all)?;
     }
-    println!("\n");
+
+    Ok(state.corrupted_members)
 }
 
-const GZIP_HEADER_SIZE: usize = 10;  // Standard GZIP header size
-const GZIP_FOOTER_SIZE: usize = 8;   // CRC32 (4 bytes) + ISIZE (4 bytes)
-const CRC32_SIZE: usize = 4;
-const ISIZE_SIZE: usize = 4;
+/// Like `inspect_file`, but decodes members across `jobs` worker threads.
+/// A single sequential pass over the file first indexes each member's
+/// start offset with `scan_member_offsets` (unavoidable, since a member's
+/// start is only known once its predecessor has been decoded, but now
+/// linear rather than quadratic). That scan already runs `read_chunk` on
+/// every member; the per-member decode that builds each `ChunkInfo` runs
+/// `read_chunk` again, independently per member once offsets are known,
+/// so it fans out across the thread pool.
+///
+/// Workers stream completed members back over a channel rather than
+/// writing into a shared results buffer, and the main thread prints (and
+/// drops each member's decompressed bytes) in member order as results
+/// arrive, instead of waiting for every worker to finish first. Peak
+/// memory is not bounded by `jobs`: members that finish ahead of an
+/// earlier, slower one wait in `pending` with their decompressed bytes
+/// until that earlier member arrives.
+fn inspect_file_parallel(file_path: &str, options: &InspectOptions, jobs: usize) -> io::Result<usize> {
+    let output_format = options.output_format.as_str();
+    let preview = options.preview.as_deref();
+    let encoding = options.encoding.as_str();
+    let chunks = options.chunks.as_deref();
+    let dedup = options.dedup.as_deref();
+    let needs_all_chunks = options.repair.is_some() || options.write_index.is_some() || options.extract.is_some();
 
-#[derive(Debug)]
-struct GzipValidationError {
-    claimed_size: u64,
-    actual_size: u64,
-    error_type: &'static str,
-}
+    let file = File::open(file_path)?;
+    let file_size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
 
-fn parse_gzip_header(header: &[u8], reader: &mut impl Read) -> io::Result<GzipHeaderInfo> {
-    let mut flags = Vec::new();
-    if header[3] & 0x01 != 0 { flags.push("TEXT".to_string()); }
-    if header[3] & 0x02 != 0 { flags.push("HCRC".to_string()); }
-    if header[3] & 0x04 != 0 { flags.push("EXTRA".to_string()); }
-    if header[3] & 0x08 != 0 { flags.push("NAME".to_string()); }
-    if header[3] & 0x10 != 0 { flags.push("COMMENT".to_string()); }
-
-    let mtime = u32::from_le_bytes(header[4..8].try_into().unwrap());
-    let mtime_str = if mtime == 0 {
-        "Not set".to_string()
-    } else {
-        DateTime::from_timestamp(mtime as i64, 0)
-            .map_or("Invalid".to_string(), |dt| dt.to_string())
-    };
+    let offsets = scan_member_offsets(&mut reader)?;
 
-    let extra_flags = match header[8] {
-        2 => "max compression".to_string(),
-        4 => "fastest".to_string(),
-        _ => format!("unknown(0x{:02x})", header[8]),
-    };
+    let progress = ProgressBar::new(offsets.len() as u64).with_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] decoding members [{bar:40.cyan/blue}] {pos}/{len}")
+            .unwrap()
+            .progress_chars("#>-")
+    );
+    progress.set_draw_target(indicatif::ProgressDrawTarget::stderr());
 
-    let os = match header[9] {
-        0 => "FAT".to_string(),
-        1 => "Amiga".to_string(),
-        2 => "VMS".to_string(),
-        3 => "Unix".to_string(),
-        4 => "VM/CMS".to_string(),
-        5 => "Atari TOS".to_string(),
-        6 => "HPFS".to_string(),
-        7 => "Macintosh".to_string(),
-        8 => "Z-System".to_string(),
-        9 => "CP/M".to_string(),
-        10 => "TOPS-20".to_string(),
-        11 => "NTFS".to_string(),
-        12 => "QDOS".to_string(),
-        13 => "Acorn RISCOS".to_string(),
-        255 => "unknown".to_string(),
-        x => format!("unknown({})", x),
-    };
+    let preview_settings = PreviewSettings::parse(preview);
+    let chunk_filter = ChunkFilterSettings::parse(chunks);
+    let chunker_settings = ChunkerSettings::parse(dedup);
+    // `--extract` writes raw extracted bytes to stdout, so none of the
+    // per-member/summary/report output below can share that stream.
+    let extract_active = options.extract.is_some();
 
-    let mut extra_fields = Vec::new();
-    let mut filename = None;
-    let mut comment = None;
-
-    // Read extra fields if present
-    if header[3] & 0x04 != 0 {
-        let mut xlen_bytes = [0u8; 2];
-        reader.read_exact(&mut xlen_bytes)?;
-        let xlen = u16::from_le_bytes(xlen_bytes);
-        let mut extra = vec![0u8; xlen as usize];
-        reader.read_exact(&mut extra)?;
-        
-        let mut pos = 0;
-        while pos + 4 <= extra.len() {
-            let si1 = extra[pos];
-            let si2 = extra[pos + 1];
-            let len = u16::from_le_bytes(extra[pos+2..pos+4].try_into().unwrap());
-            let data = if pos + 4 + len as usize <= extra.len() {
-                extra[pos+4..pos+4+len as usize].to_vec()
-            } else {
-                Vec::new()
-            };
-            extra_fields.push(((si1 as u16) << 8 | si2 as u16, data));
-            pos += 4 + len as usize;
-        }
-    }
+    let mut state = ScanState::default();
+    let mut dedup_buffer = chunker_settings.as_ref().map(|_| Vec::new());
+    let mut all_chunks: Vec<ChunkInfo> = Vec::new();
 
-    // Read filename if present
-    if header[3] & 0x08 != 0 {
-        let mut fname = Vec::new();
-        let mut buf = [0u8; 1];
-        while reader.read_exact(&mut buf).is_ok() && buf[0] != 0 {
-            fname.push(buf[0]);
+    let mut tail_buffer = chunk_filter.as_ref()
+        .and_then(|f| f.tail_chunks)
+        .map(TailBuffer::new);
+
+    let next_index = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel::<io::Result<(usize, ChunkInfo)>>();
+    let mut worker_error = None;
+
+    std::thread::scope(|scope| -> io::Result<()> {
+        for _ in 0..jobs.min(offsets.len().max(1)) {
+            let offsets = &offsets;
+            let next_index = &next_index;
+            let progress = &progress;
+            let tx = tx.clone();
+            scope.spawn(move || {
+                let mut reader = match File::open(file_path) {
+                    Ok(file) => BufReader::new(file),
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                };
+                loop {
+                    let i = next_index.fetch_add(1, Ordering::SeqCst);
+                    if i >= offsets.len() {
+                        return;
+                    }
+                    match read_chunk(&mut reader, offsets[i], i) {
+                        Ok(info) => {
+                            progress.inc(1);
+                            if tx.send(Ok((i, info))).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            let _ = tx.send(Err(e));
+                            return;
+                        }
+                    }
+                }
+            });
         }
-        filename = String::from_utf8(fname).ok();
-    }
+        // Drop our own sender so `rx` only reports "closed" once every
+        // worker's clone has also been dropped (i.e. all workers done).
+        drop(tx);
+
+        // Workers finish members out of order; buffer out-of-order arrivals
+        // here and drain them in member order as the gap closes, so
+        // head/tail filtering, dedup chunking, and printing all see
+        // members in the same order the sequential path would.
+        let mut pending: HashMap<usize, ChunkInfo> = HashMap::new();
+        let mut next_to_emit = 0usize;
+
+        while let Ok(result) = rx.recv() {
+            let (i, info) = match result {
+                Ok(pair) => pair,
+                Err(e) => {
+                    worker_error = Some(e);
+                    break;
+                }
+            };
+            pending.insert(i, info);
+
+            while let Some(mut chunk_info) = pending.remove(&next_to_emit) {
+                let chunk_number = next_to_emit;
+                next_to_emit += 1;
+
+                finish_chunk(&mut chunk_info, &preview_settings, encoding, &mut dedup_buffer, &mut state);
+                // The decompressed bytes have now served their only
+                // purposes (dedup chunking, preview rendering); drop them
+                // so a large archive doesn't pile up in memory across the
+                // whole run. None of repair, the .gzi index or extract
+                // below needs them: repair and extract re-read raw bytes
+                // from the file by offset, and the index uses only each
+                // member's offset and sizes.
+                chunk_info.preview_data = None;
+
+                let should_print = should_print_chunk(chunk_filter.as_ref(), &mut tail_buffer, chunk_number, &chunk_info);
+
+                if should_print && !extract_active {
+                    print_chunk_info(&chunk_info, output_format)?;
+                }
 
-    // Read comment if present
-    if header[3] & 0x10 != 0 {
-        let mut comment_bytes = Vec::new();
-        let mut buf = [0u8; 1];
-        while reader.read_exact(&mut buf).is_ok() && buf[0] != 0 {
-            comment_bytes.push(buf[0]);
+                if needs_all_chunks {
+                    all_chunks.push(chunk_info);
+                }
+            }
+
+            if worker_error.is_some() {
+                break;
+            }
         }
-        comment = String::from_utf8(comment_bytes).ok();
-    }
 
-    Ok(GzipHeaderInfo {
-        compression_method: match header[2] {
-            8 => "deflate".to_string(),
-            _ => format!("unknown({})", header[2]),
-        },
-        flags,
-        mtime: mtime_str,
-        extra_flags,
-        os,
-        extra_fields,
-        filename,
-        comment,
-    })
-}
+        Ok::<(), io::Error>(())
+    })?;
+
+    progress.finish_and_clear();
 
-fn validate_gzip_chunk(data: &[u8]) -> io::Result<(usize, u32)> {
-    if data.len() < GZIP_HEADER_SIZE + GZIP_FOOTER_SIZE {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk too small"));
+    if let Some(e) = worker_error {
+        return Err(e);
     }
 
-    // Check header magic
-    if data[0] != 0x1f || data[1] != 0x8b {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid header magic"));
+    // Print buffered tail chunks
+    if let Some(buffer) = tail_buffer {
+        if !extract_active {
+            if offsets.len() > buffer.capacity && output_format == "human" {
+                println!("          ...");
+            }
+            for chunk in buffer.get_buffered() {
+                print_chunk_info(chunk, output_format)?;
+            }
+        }
     }
 
-    // Get the stored values from footer
-    let footer_start = data.len() - GZIP_FOOTER_SIZE;
-    let stored_crc32 = u32::from_le_bytes(data[footer_start..footer_start + 4].try_into().unwrap());
-    let stored_size = u32::from_le_bytes(data[footer_start + 4..].try_into().unwrap());
+    if !extract_active {
+        print_summary(&state.summary(offsets.len()), output_format)?;
 
-    Ok((stored_size as usize, stored_crc32))
-}
+        if state.saw_bgzf && output_format == "human" {
+            print_bgzf_eof_status(file_path, file_size)?;
+        }
 
-fn validate_member(data: &[u8]) -> bool {
-    if data.len() < GZIP_HEADER_SIZE + GZIP_FOOTER_SIZE {
-        return false;
+        print_dedup_report(chunker_settings.as_ref(), dedup_buffer.as_ref(), output_format);
     }
-    
-    // Check header magic
-    if data[0] != 0x1f || data[1] != 0x8b || data[2] != 0x08 {
-        return false;
+
+    if needs_all_chunks {
+        write_post_scan_outputs(file_path, options, &all_chunks)?;
     }
-    
-    // Try quick decompression to validate
-    let mut decoder = GzDecoder::new(data);
-    let mut buf = Vec::new();
-    decoder.read_to_end(&mut buf).is_ok()
+
+    Ok(state.corrupted_members)
 }
 
-fn is_complete_gzip_member(data: &[u8], is_final: bool) -> bool {
-    if data.len() < GZIP_HEADER_SIZE + GZIP_FOOTER_SIZE {
-        return false;
-    }
+/// Mirrors `inspect_file`'s member loop, but reads members from `stdin`
+/// via `ChunkStream` instead of seeking a real file. `--repair`,
+/// `--write-index`, and `--extract` are rejected here rather than silently
+/// ignored; `--repair` and `--extract` need to reread raw bytes from the
+/// original file.
+fn inspect_stream(options: &InspectOptions) -> io::Result<usize> {
+    if options.repair.is_some() || options.write_index.is_some() || options.extract.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--repair, --write-index, and --extract require a seekable file, not stdin",
+        ));
+    }
+
+    let output_format = options.output_format.as_str();
+    let preview = options.preview.as_deref();
+    let encoding = options.encoding.as_str();
+    let chunks = options.chunks.as_deref();
+    let dedup = options.dedup.as_deref();
+
+    let stdin = io::stdin();
+    let mut chunk_stream = ChunkStream::new(stdin.lock());
+
+    // The total length is unknown up front when reading from a pipe, so
+    // show a spinner instead of the determinate byte-count bar.
+    let progress = ProgressBar::new_spinner().with_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} [{elapsed_precise}] {msg}")
+            .unwrap()
+    );
+    progress.set_draw_target(indicatif::ProgressDrawTarget::stderr());
 
-    // Check magic numbers and compression method
-    if data[0] != 0x1f || data[1] != 0x8b || data[2] != 0x08 {
-        return false;
-    }
+    let mut chunk_number = 0;
+    let mut total_compressed_size = 0;
+    let mut total_uncompressed_size = 0;
+    let mut corrupted_members = 0;
+    let mut zlib_members = 0;
+    let preview_settings = PreviewSettings::parse(preview);
+    let chunk_filter = ChunkFilterSettings::parse(chunks);
+    let chunker_settings = ChunkerSettings::parse(dedup);
+    let mut dedup_buffer = chunker_settings.as_ref().map(|_| Vec::new());
 
-    // For non-final chunks, be strict about validation
-    if !is_final {
-        let footer_start = data.len() - GZIP_FOOTER_SIZE;
-        let stored_size = u32::from_le_bytes(data[footer_start + 4..].try_into().unwrap());
-        let mut decoder = GzDecoder::new(data);
-        let mut buf = Vec::with_capacity(stored_size as usize);
-        return decoder.read_to_end(&mut buf).is_ok()
-    }
+    let mut tail_buffer = chunk_filter.as_ref()
+        .and_then(|f| f.tail_chunks)
+        .map(|tail| TailBuffer::new(tail));
 
-    // For final chunk, just try to decompress what we have
-    let mut decoder = GzDecoder::new(data);
-    let mut buf = Vec::new();
-    decoder.read_to_end(&mut buf).is_ok()
-}
+    loop {
+        let mut chunk_info = match chunk_stream.next_chunk(chunk_number) {
+            Ok(Some(info)) => info,
+            Ok(None) => break,
+            Err(e) => {
+                progress.finish_and_clear();
+                return Err(e);
+            }
+        };
 
-fn read_chunk<R: Read + Seek>(reader: &mut R, offset: u64, chunk_number: usize) -> io::Result<ChunkInfo> {
-    reader.seek(SeekFrom::Start(offset))?;
-    
-    // Read initial header
-    let mut header = [0u8; GZIP_HEADER_SIZE];
-    if reader.read_exact(&mut header).is_err() {
-        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "End of file"));
-    }
+        progress.set_message(format!("{} bytes read", chunk_info.offset + chunk_info.compressed_size));
+        progress.tick();
 
-    // Validate GZIP magic numbers
-    if header[0] != 0x1f || header[1] != 0x8b {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, 
-            format!("Invalid GZIP header: {:02x} {:02x} {:02x}", header[0], header[1], header[2])));
-    }
+        if let (Some(buf), Some(data)) = (dedup_buffer.as_mut(), chunk_info.preview_data.as_ref()) {
+            buf.extend_from_slice(data);
+        }
 
-    let header_info = parse_gzip_header(&header, reader)?;
-    
-    let mut compressed_data = Vec::with_capacity(8192);
-    compressed_data.extend_from_slice(&header);
-    
-    let mut buffer = [0u8; 8192];
-    let mut found_next = false;
-    
-    'read_loop: loop {
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
+        if chunk_info.integrity == Some(false) {
+            corrupted_members += 1;
         }
 
-        // Look for next GZIP header
-        for i in 0..bytes_read {
-            if bytes_read - i >= 2 && buffer[i] == 0x1f && i + 1 < bytes_read && buffer[i + 1] == 0x8b {
-                // Save current position
-                let current_pos = reader.stream_position()?;
-                
-                // Try to validate current chunk up to this point
-                let mut test_data = compressed_data.clone();
-                test_data.extend_from_slice(&buffer[..i]);
-                
-                let mut decoder = GzDecoder::new(&test_data[..]);
-                let mut test_buf = Vec::new();
-                
-                if decoder.read_to_end(&mut test_buf).is_ok() {
-                    // Valid chunk found
-                    compressed_data = test_data;
-                    reader.seek(SeekFrom::Start(offset + compressed_data.len() as u64))?;
-                    found_next = true;
-                    break 'read_loop;
-                }
-                
-                // If validation failed, restore position and continue
-                reader.seek(SeekFrom::Start(current_pos))?;
-            }
+        if chunk_info.format == Format::Zlib {
+            zlib_members += 1;
         }
 
-        compressed_data.extend_from_slice(&buffer[..bytes_read]);
-        
-        // Safety limit with a more generous size for last chunk
-        if compressed_data.len() > 20 * 1024 * 1024 {
-            // Try to decompress what we have so far
-            let mut decoder = GzDecoder::new(&compressed_data[..]);
-            let mut test_buf = Vec::new();
-            if decoder.read_to_end(&mut test_buf).is_ok() {
-                break;
+        if let Some(settings) = &preview_settings {
+            if let Some(data) = &chunk_info.preview_data {
+                chunk_info.preview = Some(render_preview(data, settings, encoding));
             }
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk too large"));
         }
-    }
 
-    // Handle last chunk
-    if !found_next {
-        // Try to decompress full chunk first
-        let mut decoder = GzDecoder::new(&compressed_data[..]);
-        let mut test_buf = Vec::new();
-        if decoder.read_to_end(&mut test_buf).is_err() {
-            // If full decompression fails, try to find a valid ending
-            for i in (GZIP_HEADER_SIZE..compressed_data.len()).rev() {
-                let test_slice = &compressed_data[..i];
-                let mut decoder = GzDecoder::new(test_slice);
-                let mut test_buf = Vec::new();
-                if decoder.read_to_end(&mut test_buf).is_ok() {
-                    compressed_data.truncate(i);
-                    break;
+        let should_print = should_print_chunk(chunk_filter.as_ref(), &mut tail_buffer, chunk_number, &chunk_info);
+
+        if should_print {
+            if output_format == "json" {
+                print!("{}", serde_json::to_string(&chunk_info)?);
+                println!();
+            } else {
+                println!("{}", chunk_info);
+                if let Some(preview) = &chunk_info.preview {
+                    print_preview(preview);
                 }
             }
         }
-    }
 
-    // Final decompression attempt
-    let mut decoder = GzDecoder::new(&compressed_data[..]);
-    let mut decompressed = Vec::new();
-    
-    match decoder.read_to_end(&mut decompressed) {
-        Ok(size) => Ok(ChunkInfo {
-            chunk_number,
-            offset,
-            compressed_size: compressed_data.len() as u64,
-            uncompressed_size: size as u64,
-            compression_ratio: size as f64 / compressed_data.len() as f64,
-            header_info: header_info.to_string(),
-            preview_data: Some(decompressed),
-        }),
-        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, 
-            format!("Decompression error at offset {}: {}", offset, e)))
+        total_compressed_size += chunk_info.compressed_size;
+        total_uncompressed_size += chunk_info.uncompressed_size;
+        chunk_number += 1;
     }
-}
 
-fn count_chunks(file_path: &str) -> io::Result<usize> {
-    let file = File::open(file_path)?;
-    let mut reader = BufReader::new(file);
-    let mut offset = 0;
-    let mut count = 0;
+    progress.finish_and_clear();
 
-    loop {
-        match read_chunk(&mut reader, offset, count) {
-            Ok(info) => {
-                offset += info.compressed_size;
-                count += 1;
+    // Print buffered tail chunks
+    if let Some(buffer) = tail_buffer {
+        if chunk_number > buffer.capacity {
+            if output_format == "human" {
+                println!("          ...");
+            }
+        }
+        for chunk in buffer.get_buffered() {
+            if output_format == "json" {
+                print!("{}", serde_json::to_string(chunk)?);
+                println!();
+            } else {
+                println!("{}", chunk);
+                if let Some(preview) = &chunk.preview {
+                    print_preview(preview);
+                }
             }
-            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
-            Err(e) => return Err(e),
         }
     }
 
-    Ok(count)
-}
+    // Print summary
+    let summary = FileSummary {
+        total_chunks: chunk_number,
+        total_compressed_size,
+        total_uncompressed_size,
+        average_compression_ratio: total_uncompressed_size as f64 / total_compressed_size as f64,
+        corrupted_members,
+        zlib_members,
+    };
 
-fn find_gzip_header(buffer: &[u8]) -> Option<usize> {
-    for i in 0..buffer.len() - 1 {
-        if buffer[i] == 0x1f && buffer[i + 1] == 0x8b {
-            return Some(i);
+    if output_format == "json" {
+        println!("{}", serde_json::to_string(&summary)?);
+    } else {
+        println!("{}", summary);
+    }
+
+    // `inspect_file`'s BGZF EOF-marker check seeks to the end of the
+    // file, which a pipe can't do, so it's skipped here.
+
+    // Print dedup analysis, if requested
+    if let (Some(settings), Some(data)) = (chunker_settings.as_ref(), dedup_buffer.as_ref()) {
+        let report = analyze(data, settings);
+        if output_format == "human" {
+            println!("{}", report);
         }
     }
-    None
+
+    Ok(corrupted_members)
 }
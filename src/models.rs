@@ -8,11 +8,47 @@ pub struct ChunkInfo {
     pub uncompressed_size: u64,
     pub compression_ratio: f64,
     pub header_info: String,
+    pub integrity: Option<bool>,
+    pub validation: Option<GzipValidationError>,
+    pub bgzf_block_size: Option<u32>,
+    pub bgzf_virtual_offset: Option<u64>,
+    pub format: Format,
+    pub window_size: Option<u32>,
+    pub compression_level: Option<String>,
+    pub dictionary_id: Option<u32>,
+    /// RFC 1952 trailer fields; `None` for zlib members, which trail with
+    /// an Adler-32 instead of CRC32 + ISIZE. Whether the trailer actually
+    /// matches is not stored separately — compare `isize_expected` against
+    /// `uncompressed_size`, or see `integrity`/`validation` for the
+    /// interpreted verdict (which also folds in BGZF's own size check).
+    pub crc32_expected: Option<u32>,
+    pub crc32_actual: Option<u32>,
+    pub isize_expected: Option<u32>,
+    pub preview: Option<String>,
     #[serde(skip)]
     pub preview_data: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Serialize)]
+/// Which RFC the member's header/trailer follow: gzip (RFC 1952, the
+/// common case) or bare zlib (RFC 1950), as used by e.g. zlib-compressed
+/// HTTP bodies or PDF streams.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Gzip,
+    Zlib,
+}
+
+/// Why a member failed validation: its trailer didn't match the data
+/// actually decompressed, its header was malformed or failed its CRC16, or
+/// its BGZF block size didn't match the member's length.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct GzipValidationError {
+    pub claimed_size: u64,
+    pub actual_size: u64,
+    pub error_type: &'static str,
+}
+
+#[derive(Debug, Serialize, Clone)]
 pub struct GzipHeaderInfo {
     pub compression_method: String,
     pub flags: Vec<String>,
@@ -22,6 +58,19 @@ pub struct GzipHeaderInfo {
     pub extra_fields: Vec<(u16, Vec<u8>)>,
     pub filename: Option<String>,
     pub comment: Option<String>,
+    pub header_crc_expected: Option<u16>,
+    pub header_crc_actual: Option<u16>,
+    pub header_crc_ok: Option<bool>,
+    /// `false` if any of FLG's three reserved bits (0xE0) are set, which
+    /// RFC 1952 forbids — a sign of a corrupt or non-conforming stream.
+    pub reserved_flags_valid: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZlibHeaderInfo {
+    pub window_size: u32,
+    pub compression_level: String,
+    pub dictionary_id: Option<u32>,
 }
 
 #[derive(Serialize, Debug)]
@@ -30,6 +79,8 @@ pub struct FileSummary {
     pub total_compressed_size: u64,
     pub total_uncompressed_size: u64,
     pub average_compression_ratio: f64,
+    pub corrupted_members: usize,
+    pub zlib_members: usize,
 }
 
 pub struct PreviewSettings {
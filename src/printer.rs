@@ -1,27 +1,107 @@
-use crate::models::{PreviewSettings, ChunkInfo, FileSummary, GzipHeaderInfo};
+use crate::models::{PreviewSettings, ChunkInfo, FileSummary, GzipHeaderInfo, ZlibHeaderInfo};
 use std::fmt;
 
-pub fn print_preview(data: &[u8], settings: &PreviewSettings, _encoding: &str) {
-    let text = String::from_utf8_lossy(data).into_owned();
+const HEX_ROW_WIDTH: usize = 16;
+
+/// Render the head/tail preview of `data` as text, honoring `encoding`
+/// (`utf-8`, `latin1`, `utf-16le`, `utf-16be`, or `hex`). This is the single
+/// source of truth for preview content: callers print it as-is and also
+/// stash it in `ChunkInfo::preview` for JSON output.
+pub fn render_preview(data: &[u8], settings: &PreviewSettings, encoding: &str) -> String {
+    if encoding == "hex" {
+        render_hex_preview(data, settings)
+    } else {
+        render_text_preview(data, settings, encoding)
+    }
+}
+
+pub fn print_preview(preview: &str) {
+    print!("{}", preview);
+    println!();
+}
+
+fn decode_text(data: &[u8], encoding: &str) -> String {
+    match encoding {
+        "latin1" => data.iter().map(|&b| b as char).collect(),
+        "utf-16le" => decode_utf16(data, u16::from_le_bytes),
+        "utf-16be" => decode_utf16(data, u16::from_be_bytes),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+fn decode_utf16(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = data.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn render_text_preview(data: &[u8], settings: &PreviewSettings, encoding: &str) -> String {
+    let text = decode_text(data, encoding);
     let lines: Vec<&str> = text.lines().collect();
-    
-    // Print head lines
+    let mut out = String::new();
+
+    // Head lines
     let head = settings.head_lines.min(lines.len());
     for (i, line) in lines[..head].iter().enumerate() {
-        println!("     {:>4} │ {}", i + 1, line);
+        out.push_str(&format!("     {:>4} │ {}\n", i + 1, line));
     }
-    
-    // Print tail lines if requested
+
+    // Tail lines if requested
     if let Some(tail_count) = settings.tail_lines {
         if head < lines.len() {
-            println!("          | ...");
+            out.push_str("          | ...\n");
             let start = lines.len().saturating_sub(tail_count);
             for (i, line) in lines[start..].iter().enumerate() {
-                println!("     {:>4} │ {}", start + i + 1, line);
+                out.push_str(&format!("     {:>4} │ {}\n", start + i + 1, line));
             }
         }
     }
-    println!("\n");
+    out
+}
+
+/// Render `data` as an `xxd`-style offset+hex+ASCII dump, `HEX_ROW_WIDTH`
+/// bytes per row, taking `settings.head_lines`/`tail_lines` as row counts
+/// rather than line counts.
+fn render_hex_preview(data: &[u8], settings: &PreviewSettings) -> String {
+    let total_rows = data.len().div_ceil(HEX_ROW_WIDTH);
+    let mut out = String::new();
+
+    let head_rows = settings.head_lines.min(total_rows);
+    for row in 0..head_rows {
+        out.push_str(&hex_row(data, row));
+    }
+
+    if let Some(tail_count) = settings.tail_lines {
+        if head_rows < total_rows {
+            out.push_str("          | ...\n");
+            let start = total_rows.saturating_sub(tail_count).max(head_rows);
+            for row in start..total_rows {
+                out.push_str(&hex_row(data, row));
+            }
+        }
+    }
+    out
+}
+
+fn hex_row(data: &[u8], row: usize) -> String {
+    let offset = row * HEX_ROW_WIDTH;
+    let end = (offset + HEX_ROW_WIDTH).min(data.len());
+    let bytes = &data[offset..end];
+
+    let mut hex = String::new();
+    for (i, b) in bytes.iter().enumerate() {
+        if i == HEX_ROW_WIDTH / 2 {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{:02x} ", b));
+    }
+
+    let ascii: String = bytes.iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect();
+
+    format!("     {:08x} │ {:<49}│ {}\n", offset, hex, ascii)
 }
 
 
@@ -46,31 +126,63 @@ pub fn human_size(size: u64) -> String {
 
 impl fmt::Display for GzipHeaderInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}|{}", 
+        write!(f, "{}|{}",
             self.compression_method,
             self.flags.join("|"))?;
         if let Some(fname) = &self.filename {
             write!(f, "|{}", fname)?;
         }
+        if self.header_crc_ok == Some(false) {
+            write!(f, "|HCRC BAD")?;
+        }
+        if !self.reserved_flags_valid {
+            write!(f, "|RESERVED BITS SET")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ZlibHeaderInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "zlib|window:{}|level:{}", self.window_size, self.compression_level)?;
+        if let Some(id) = self.dictionary_id {
+            write!(f, "|dictid:{:08x}", id)?;
+        }
         Ok(())
     }
 }
 
 impl fmt::Display for ChunkInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let ratio = if self.compression_ratio >= 1.0 {
+        let ratio = if self.uncompressed_size == 0 {
+            // A zero-byte member (e.g. BGZF's trailing empty EOF block) has
+            // no meaningful compression ratio; avoid dividing by zero.
+            "🔒 n/a".to_string()
+        } else if self.compression_ratio >= 1.0 {
             format!("🔓 {:.1}x", self.compression_ratio)
         } else {
             format!("🔒 {:.1}x", 1.0 / self.compression_ratio)
         };
+        let integrity = match (&self.integrity, &self.validation) {
+            (Some(true), _) => " ✅".to_string(),
+            (Some(false), Some(err)) => format!(" ❌ ({})", err.error_type),
+            (Some(false), None) => " ❌".to_string(),
+            (None, _) => String::new(),
+        };
+        let bgzf = self.bgzf_block_size
+            .map(|size| format!(" │ 🧬 BGZF block ({} bytes, voffset {})",
+                size, self.bgzf_virtual_offset.unwrap_or(0)))
+            .unwrap_or_default();
 
-        write!(f, "📦 #{:<5} │ 📍 {:<10} │ {} │ 📥 {:<8} │ 📤 {:<8} │ ℹ️  {}",
+        write!(f, "📦 #{:<5} │ 📍 {:<10} │ {} │ 📥 {:<8} │ 📤 {:<8} │ ℹ️  {}{}{}",
             self.chunk_number,
             self.offset,
             ratio,
             human_size(self.compressed_size),
             human_size(self.uncompressed_size),
-            self.header_info)
+            self.header_info,
+            integrity,
+            bgzf)
     }
 }
 
@@ -80,7 +192,9 @@ impl fmt::Display for FileSummary {
         write!(f, "├─ 📦 Chunks: {}\n", self.total_chunks)?;
         write!(f, "├─ 📥 Total Compressed: {}\n", human_size(self.total_compressed_size))?;
         write!(f, "├─ 📤 Total Uncompressed: {}\n", human_size(self.total_uncompressed_size))?;
-        write!(f, "└─ 📈 Average Compression: {:.1}x", self.average_compression_ratio)
+        write!(f, "├─ 📈 Average Compression: {:.1}x\n", self.average_compression_ratio)?;
+        write!(f, "├─ 🧪 Zlib Members: {}\n", self.zlib_members)?;
+        write!(f, "└─ 🛡️  Corrupted Members: {}", self.corrupted_members)
     }
 }
 
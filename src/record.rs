@@ -0,0 +1,357 @@
+//! Resumable streaming member iterator.
+//!
+//! `MemberRecords` drives gzip member parsing as a small state machine and
+//! yields one typed `MemberRecord` at a time instead of buffering an
+//! entire file up front. Each record carries the byte offset it starts
+//! at, so downstream code can work on streams of unbounded size (e.g.
+//! stdin) rather than requiring a seekable file.
+//!
+//! This is also the buffering primitive `stream::ChunkStream` builds on:
+//! rather than re-deriving its own rolling-buffer/deflate-consumption
+//! logic, `ChunkStream` drives a `MemberRecords` for the gzip path and
+//! reuses its `take_bytes`/`consume_deflate`/`peek_magic` helpers for zlib
+//! framing, which isn't itself a gzip record type.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
+
+use flate2::{Decompress, FlushDecompress, Status};
+
+use crate::gzip::parse_gzip_header;
+use crate::models::GzipHeaderInfo;
+
+const GZIP_HEADER_SIZE: usize = 10;
+const GZIP_FOOTER_SIZE: usize = 8;
+const READ_CHUNK: usize = 8192;
+
+#[derive(Debug, Clone)]
+pub enum MemberRecord {
+    Header(GzipHeaderInfo),
+    Deflate { compressed_len: u64, data: Vec<u8> },
+    Trailer { crc32: u32, isize: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordEvent {
+    pub offset: u64,
+    pub record: MemberRecord,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    BadRecordType { offset: u64, found: [u8; 2] },
+}
+
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        RecordError::Io(e)
+    }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Io(e) => write!(f, "{}", e),
+            RecordError::BadRecordType { offset, found } => write!(
+                f,
+                "unexpected record type 0x{:02x}{:02x} at offset {}",
+                found[0], found[1], offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Deflate,
+    Trailer,
+    Done,
+}
+
+/// A small state machine that reads gzip members one record at a time
+/// from any `Read`, buffering only as much as it needs to look ahead for
+/// the next record boundary.
+pub struct MemberRecords<R> {
+    reader: R,
+    pending: VecDeque<u8>,
+    offset: u64,
+    state: State,
+}
+
+impl<R: Read> MemberRecords<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: VecDeque::new(),
+            offset: 0,
+            state: State::Header,
+        }
+    }
+
+    fn fill(&mut self, want: usize) -> io::Result<()> {
+        let mut buf = [0u8; READ_CHUNK];
+        while self.pending.len() < want {
+            let n = self.reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            self.pending.extend(&buf[..n]);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn take_bytes(&mut self, n: usize) -> io::Result<Vec<u8>> {
+        self.fill(n)?;
+        if self.pending.len() < n {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated gzip stream"));
+        }
+        let bytes: Vec<u8> = self.pending.drain(..n).collect();
+        self.offset += n as u64;
+        Ok(bytes)
+    }
+
+    /// Byte offset of whatever comes next. Lets a caller driving this
+    /// iterator (e.g. `ChunkStream`) compute a member's `compressed_size`
+    /// as `offset()` before minus `offset()` after.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Look at the next two bytes without consuming them, or `None` at a
+    /// clean end of stream between members. Used to dispatch on magic
+    /// bytes before committing to the gzip record state machine (e.g. to
+    /// fall back to zlib framing instead).
+    pub(crate) fn peek_magic(&mut self) -> io::Result<Option<[u8; 2]>> {
+        self.fill(2)?;
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        if self.pending.len() < 2 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated member header"));
+        }
+        Ok(Some([self.pending[0], self.pending[1]]))
+    }
+
+    /// Consume a raw deflate stream from the front of the rolling buffer,
+    /// returning the number of compressed bytes it took and the
+    /// decompressed payload, once the decoder reports `StreamEnd`. Shared
+    /// by the gzip `Deflate` record and by `ChunkStream`'s zlib path,
+    /// since raw deflate framing doesn't depend on which container it's
+    /// wrapped in.
+    pub(crate) fn consume_deflate(&mut self) -> Result<(u64, Vec<u8>), RecordError> {
+        let start = self.offset;
+        let mut decompress = Decompress::new(false);
+        let mut decompressed = Vec::new();
+        let mut outbuf = [0u8; READ_CHUNK];
+
+        loop {
+            self.fill(READ_CHUNK)?;
+            if self.pending.is_empty() {
+                return Err(RecordError::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated deflate stream",
+                )));
+            }
+
+            let chunk: Vec<u8> = self.pending.iter().copied().collect();
+            let before_in = decompress.total_in();
+            let before_out = decompress.total_out();
+            let status = decompress
+                .decompress(&chunk, &mut outbuf, FlushDecompress::None)
+                .map_err(|e| RecordError::Io(io::Error::new(io::ErrorKind::InvalidData, e.to_string())))?;
+            let consumed = (decompress.total_in() - before_in) as usize;
+            let produced = (decompress.total_out() - before_out) as usize;
+            decompressed.extend_from_slice(&outbuf[..produced]);
+
+            for _ in 0..consumed {
+                self.pending.pop_front();
+            }
+            self.offset += consumed as u64;
+
+            if status == Status::StreamEnd {
+                break;
+            }
+            if consumed == 0 && produced == 0 && self.pending.len() == chunk.len() {
+                return Err(RecordError::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated deflate stream",
+                )));
+            }
+        }
+
+        Ok((self.offset - start, decompressed))
+    }
+
+    fn read_header(&mut self) -> Result<Option<RecordEvent>, RecordError> {
+        self.fill(1)?;
+        if self.pending.is_empty() {
+            self.state = State::Done;
+            return Ok(None);
+        }
+
+        let record_offset = self.offset;
+        let magic = self.take_bytes(2)?;
+        if magic[0] != 0x1f || magic[1] != 0x8b {
+            self.state = State::Done;
+            return Err(RecordError::BadRecordType {
+                offset: record_offset,
+                found: [magic[0], magic[1]],
+            });
+        }
+
+        let rest = self.take_bytes(GZIP_HEADER_SIZE - 2)?;
+        let mut header = [0u8; GZIP_HEADER_SIZE];
+        header[..2].copy_from_slice(&magic);
+        header[2..].copy_from_slice(&rest);
+
+        let info = {
+            let mut source = PendingSource { owner: self };
+            parse_gzip_header(&header, &mut source)?
+        };
+
+        self.state = State::Deflate;
+        Ok(Some(RecordEvent {
+            offset: record_offset,
+            record: MemberRecord::Header(info),
+        }))
+    }
+
+    fn read_deflate(&mut self) -> Result<RecordEvent, RecordError> {
+        let record_offset = self.offset;
+        let (compressed_len, data) = self.consume_deflate()?;
+        self.state = State::Trailer;
+        Ok(RecordEvent {
+            offset: record_offset,
+            record: MemberRecord::Deflate { compressed_len, data },
+        })
+    }
+
+    fn read_trailer(&mut self) -> Result<RecordEvent, RecordError> {
+        let record_offset = self.offset;
+        let bytes = self.take_bytes(GZIP_FOOTER_SIZE)?;
+        let crc32 = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let isize = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        self.state = State::Header;
+        Ok(RecordEvent {
+            offset: record_offset,
+            record: MemberRecord::Trailer { crc32, isize },
+        })
+    }
+}
+
+impl<R: Read> Iterator for MemberRecords<R> {
+    type Item = Result<RecordEvent, RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            State::Done => None,
+            State::Header => self.read_header().transpose(),
+            State::Deflate => Some(self.read_deflate()),
+            State::Trailer => Some(self.read_trailer()),
+        }
+    }
+}
+
+/// Adapts `MemberRecords`' internal pending buffer as a `Read` so
+/// `parse_gzip_header` can consume the optional EXTRA/NAME/COMMENT
+/// fields without the iterator needing to know their format.
+struct PendingSource<'a, R> {
+    owner: &'a mut MemberRecords<R>,
+}
+
+impl<'a, R: Read> Read for PendingSource<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.owner.fill(buf.len().max(1))?;
+        let n = buf.len().min(self.owner.pending.len());
+        for slot in buf.iter_mut().take(n) {
+            *slot = self.owner.pending.pop_front().unwrap();
+        }
+        self.owner.offset += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn make_gzip_member(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn walks_two_members_as_header_deflate_trailer_triples() {
+        let first = make_gzip_member(b"hello");
+        let second = make_gzip_member(b"a second member");
+        let mut combined = first.clone();
+        combined.extend_from_slice(&second);
+
+        let mut records = MemberRecords::new(Cursor::new(combined));
+
+        for expected_data in [b"hello".to_vec(), b"a second member".to_vec()] {
+            assert!(matches!(records.next(), Some(Ok(RecordEvent { record: MemberRecord::Header(_), .. }))));
+            match records.next() {
+                Some(Ok(RecordEvent { record: MemberRecord::Deflate { data, .. }, .. })) => {
+                    assert_eq!(data, expected_data);
+                }
+                other => panic!("expected Deflate record, got {:?}", other),
+            }
+            assert!(matches!(records.next(), Some(Ok(RecordEvent { record: MemberRecord::Trailer { .. }, .. }))));
+        }
+
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn stops_cleanly_with_no_bytes_pending() {
+        let mut records = MemberRecords::new(Cursor::new(Vec::new()));
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn truncated_header_yields_an_io_error() {
+        // A single byte can never be a complete 10-byte gzip header, let
+        // alone a complete 2-byte magic.
+        let mut records = MemberRecords::new(Cursor::new(vec![0x1f]));
+        match records.next() {
+            Some(Err(RecordError::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected a truncated-stream IO error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated_trailer_yields_an_io_error() {
+        let mut member = make_gzip_member(b"hello");
+        // Drop everything from the middle of the 8-byte trailer onward.
+        member.truncate(member.len() - 4);
+        let mut records = MemberRecords::new(Cursor::new(member));
+
+        assert!(matches!(records.next(), Some(Ok(RecordEvent { record: MemberRecord::Header(_), .. }))));
+        assert!(matches!(records.next(), Some(Ok(RecordEvent { record: MemberRecord::Deflate { .. }, .. }))));
+        match records.next() {
+            Some(Err(RecordError::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected a truncated-stream IO error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_gzip_magic_is_a_bad_record_type() {
+        // 0x78 0x9c is a zlib header, not a valid gzip member magic.
+        let mut records = MemberRecords::new(Cursor::new(vec![0x78, 0x9c, 0, 0, 0, 0]));
+        match records.next() {
+            Some(Err(RecordError::BadRecordType { offset, found })) => {
+                assert_eq!(offset, 0);
+                assert_eq!(found, [0x78, 0x9c]);
+            }
+            other => panic!("expected BadRecordType, got {:?}", other),
+        }
+    }
+}
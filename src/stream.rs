@@ -0,0 +1,246 @@
+//! Member detection for non-seekable sources (pipes, `stdin`).
+//!
+//! `read_chunk` seeks to each member's offset and seeks back over bytes it
+//! read past the end of a deflate stream, which a pipe can't do. `ChunkStream` instead drives a
+//! `record::MemberRecords`, which tracks exactly how many bytes the
+//! deflate decoder consumed for each member and treats whatever is left
+//! in its rolling buffer as the start of the next one — the same trick
+//! `flate2`'s own bufread gzip reader uses to walk concatenated members
+//! without seeking — and assembles its `Header`/`Deflate`/`Trailer`
+//! records into a whole `ChunkInfo` per member. zlib isn't a gzip record
+//! type, so that path drives `MemberRecords`' buffering/deflate-consuming
+//! helpers directly instead of going through records.
+
+use std::io::{self, Read};
+
+use crate::gzip::{bgzf_block_size, header_validation_error, trailer_fields, trailer_validation_error};
+use crate::index::bgzf_virtual_offset;
+use crate::models::{ChunkInfo, Format, GzipValidationError};
+use crate::record::{MemberRecord, MemberRecords, RecordError};
+use crate::zlib::{is_zlib_magic, parse_zlib_header, validate_zlib_trailer};
+
+const GZIP_FOOTER_SIZE: usize = 8;
+const ZLIB_HEADER_SIZE: usize = 2;
+const ZLIB_FOOTER_SIZE: usize = 4;
+
+fn record_error_to_io(e: RecordError) -> io::Error {
+    match e {
+        RecordError::Io(e) => e,
+        bad @ RecordError::BadRecordType { .. } => io::Error::new(io::ErrorKind::InvalidData, bad.to_string()),
+    }
+}
+
+/// Reads gzip/zlib members one at a time from any `Read`, without
+/// requiring the source to be seekable.
+pub struct ChunkStream<R> {
+    records: MemberRecords<R>,
+}
+
+impl<R: Read> ChunkStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            records: MemberRecords::new(reader),
+        }
+    }
+
+    fn next_gzip_chunk(&mut self, chunk_number: usize) -> io::Result<ChunkInfo> {
+        let record_offset = self.records.offset();
+
+        let header_info = match self.records.next() {
+            Some(Ok(event)) => match event.record {
+                MemberRecord::Header(info) => info,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected gzip header record")),
+            },
+            Some(Err(e)) => return Err(record_error_to_io(e)),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated member header")),
+        };
+
+        let decompressed = match self.records.next() {
+            Some(Ok(event)) => match event.record {
+                MemberRecord::Deflate { data, .. } => data,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected deflate record")),
+            },
+            Some(Err(e)) => return Err(record_error_to_io(e)),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated deflate stream")),
+        };
+
+        let footer = match self.records.next() {
+            Some(Ok(event)) => match event.record {
+                MemberRecord::Trailer { crc32, isize } => {
+                    let mut bytes = [0u8; GZIP_FOOTER_SIZE];
+                    bytes[0..4].copy_from_slice(&crc32.to_le_bytes());
+                    bytes[4..8].copy_from_slice(&isize.to_le_bytes());
+                    bytes
+                }
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected trailer record")),
+            },
+            Some(Err(e)) => return Err(record_error_to_io(e)),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated trailer")),
+        };
+
+        let trailer = trailer_fields(&footer, &decompressed);
+        let mut validation = header_validation_error(&header_info)
+            .or_else(|| trailer_validation_error(&trailer, decompressed.len() as u64));
+        let bgzf_block_size = bgzf_block_size(&header_info.extra_fields);
+        let compressed_size = self.records.offset() - record_offset;
+        if let Some(bsize) = bgzf_block_size {
+            if validation.is_none() && bsize as u64 != compressed_size {
+                validation = Some(GzipValidationError {
+                    claimed_size: bsize as u64,
+                    actual_size: compressed_size,
+                    error_type: "bgzf size mismatch",
+                });
+            }
+        }
+        let integrity = Some(validation.is_none());
+        let bgzf_virtual_offset = bgzf_block_size.map(|_| bgzf_virtual_offset(record_offset, 0));
+
+        Ok(ChunkInfo {
+            chunk_number,
+            offset: record_offset,
+            compressed_size,
+            uncompressed_size: decompressed.len() as u64,
+            compression_ratio: decompressed.len() as f64 / compressed_size as f64,
+            header_info: header_info.to_string(),
+            integrity,
+            validation,
+            bgzf_block_size,
+            bgzf_virtual_offset,
+            format: Format::Gzip,
+            window_size: None,
+            compression_level: None,
+            dictionary_id: None,
+            crc32_expected: Some(trailer.crc32_expected),
+            crc32_actual: Some(trailer.crc32_actual),
+            isize_expected: Some(trailer.isize_expected),
+            preview: None,
+            preview_data: Some(decompressed),
+        })
+    }
+
+    fn next_zlib_chunk(&mut self, chunk_number: usize) -> io::Result<ChunkInfo> {
+        let record_offset = self.records.offset();
+        let header_bytes = self.records.take_bytes(ZLIB_HEADER_SIZE)?;
+        let header = [header_bytes[0], header_bytes[1]];
+
+        let dictionary_id = if header[1] & 0x20 != 0 {
+            let dictid_bytes = self.records.take_bytes(4)?;
+            Some(u32::from_be_bytes(dictid_bytes.try_into().unwrap()))
+        } else {
+            None
+        };
+
+        let header_info = parse_zlib_header(header, dictionary_id);
+
+        let (_, decompressed) = self.records.consume_deflate().map_err(record_error_to_io)?;
+        let footer = self.records.take_bytes(ZLIB_FOOTER_SIZE)?;
+
+        let validation = validate_zlib_trailer(&footer, &decompressed).err();
+        let integrity = Some(validation.is_none());
+        let compressed_size = self.records.offset() - record_offset;
+
+        Ok(ChunkInfo {
+            chunk_number,
+            offset: record_offset,
+            compressed_size,
+            uncompressed_size: decompressed.len() as u64,
+            compression_ratio: decompressed.len() as f64 / compressed_size as f64,
+            window_size: Some(header_info.window_size),
+            compression_level: Some(header_info.compression_level.clone()),
+            dictionary_id: header_info.dictionary_id,
+            header_info: header_info.to_string(),
+            integrity,
+            validation,
+            bgzf_block_size: None,
+            bgzf_virtual_offset: None,
+            format: Format::Zlib,
+            crc32_expected: None,
+            crc32_actual: None,
+            isize_expected: None,
+            preview: None,
+            preview_data: Some(decompressed),
+        })
+    }
+
+    /// Read the next member, or `None` at a clean end of stream between
+    /// members.
+    pub fn next_chunk(&mut self, chunk_number: usize) -> io::Result<Option<ChunkInfo>> {
+        let magic = match self.records.peek_magic()? {
+            Some(magic) => magic,
+            None => return Ok(None),
+        };
+
+        if magic == [0x1f, 0x8b] {
+            self.next_gzip_chunk(chunk_number).map(Some)
+        } else if is_zlib_magic(magic) {
+            self.next_zlib_chunk(chunk_number).map(Some)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("Invalid GZIP/zlib header: {:02x} {:02x}", magic[0], magic[1])))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn make_gzip_member(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn make_zlib_member(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn next_chunk_walks_a_concatenated_gzip_stream() {
+        let first = make_gzip_member(b"hello");
+        let second = make_gzip_member(b"a second, somewhat longer member");
+        let mut combined = first;
+        combined.extend_from_slice(&second);
+        let mut stream = ChunkStream::new(Cursor::new(combined));
+
+        let chunk0 = stream.next_chunk(0).unwrap().unwrap();
+        assert_eq!(chunk0.format, Format::Gzip);
+        assert_eq!(chunk0.uncompressed_size, 5);
+        assert_eq!(chunk0.integrity, Some(true));
+
+        let chunk1 = stream.next_chunk(1).unwrap().unwrap();
+        assert_eq!(chunk1.uncompressed_size, b"a second, somewhat longer member".len() as u64);
+        assert_eq!(chunk1.integrity, Some(true));
+
+        assert!(stream.next_chunk(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn next_chunk_reads_a_zlib_stream() {
+        let member = make_zlib_member(b"a zlib-framed payload");
+        let mut stream = ChunkStream::new(Cursor::new(member));
+
+        let chunk = stream.next_chunk(0).unwrap().unwrap();
+        assert_eq!(chunk.format, Format::Zlib);
+        assert_eq!(chunk.uncompressed_size, b"a zlib-framed payload".len() as u64);
+        assert_eq!(chunk.integrity, Some(true));
+
+        assert!(stream.next_chunk(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn next_chunk_returns_none_on_empty_input() {
+        let mut stream = ChunkStream::new(Cursor::new(Vec::new()));
+        assert!(stream.next_chunk(0).unwrap().is_none());
+    }
+
+    #[test]
+    fn next_chunk_rejects_unrecognized_magic() {
+        let mut stream = ChunkStream::new(Cursor::new(vec![0xde, 0xad, 0, 0]));
+        let err = stream.next_chunk(0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
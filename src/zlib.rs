@@ -0,0 +1,160 @@
+//! zlib (RFC 1950) stream inspection.
+//!
+//! Mirrors the gzip member reader in `gzip.rs`, but for bare zlib streams:
+//! a 2-byte header (6 bytes with FDICT), a raw deflate payload, and a
+//! 4-byte big-endian Adler-32 trailer in place of gzip's little-endian
+//! CRC32 + ISIZE.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::gzip::consume_deflate_member;
+use crate::models::{ChunkInfo, Format, GzipValidationError, ZlibHeaderInfo};
+
+const ZLIB_HEADER_SIZE: usize = 2;
+const ZLIB_TRAILER_SIZE: usize = 4; // Adler-32
+
+/// `true` if `bytes` looks like the start of a zlib stream: CM (low
+/// nibble of CMF) is 8 (deflate), and the 16-bit big-endian `CMF*256+FLG`
+/// is a multiple of 31, per RFC 1950's FCHECK requirement.
+pub fn is_zlib_magic(bytes: [u8; 2]) -> bool {
+    bytes[0] & 0x0f == 8 && (bytes[0] as u16 * 256 + bytes[1] as u16).is_multiple_of(31)
+}
+
+/// Compute the Adler-32 checksum RFC 1950 requires in a zlib stream's
+/// trailer.
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut s1 = 1u32;
+    let mut s2 = 0u32;
+    for &byte in data {
+        s1 = (s1 + byte as u32) % MOD_ADLER;
+        s2 = (s2 + s1) % MOD_ADLER;
+    }
+    (s2 << 16) | s1
+}
+
+/// Check a zlib member's trailing 4-byte Adler-32 against the actually
+/// decompressed bytes.
+pub fn validate_zlib_trailer(compressed_data: &[u8], decompressed: &[u8]) -> Result<(), GzipValidationError> {
+    if compressed_data.len() < ZLIB_TRAILER_SIZE {
+        return Err(GzipValidationError {
+            claimed_size: 0,
+            actual_size: decompressed.len() as u64,
+            error_type: "missing trailer",
+        });
+    }
+    let footer_start = compressed_data.len() - ZLIB_TRAILER_SIZE;
+    let stored_adler32 = u32::from_be_bytes(compressed_data[footer_start..footer_start + 4].try_into().unwrap());
+    let actual_adler32 = adler32(decompressed);
+
+    if stored_adler32 != actual_adler32 {
+        return Err(GzipValidationError {
+            claimed_size: stored_adler32 as u64,
+            actual_size: actual_adler32 as u64,
+            error_type: "adler32 mismatch",
+        });
+    }
+    Ok(())
+}
+
+fn compression_level_label(flevel: u8) -> &'static str {
+    match flevel {
+        0 => "fastest",
+        1 => "fast",
+        2 => "default",
+        3 => "maximum",
+        _ => "unknown",
+    }
+}
+
+pub fn parse_zlib_header(header: [u8; ZLIB_HEADER_SIZE], dictionary_id: Option<u32>) -> ZlibHeaderInfo {
+    let cmf = header[0];
+    let flg = header[1];
+    let cinfo = cmf >> 4;
+    let flevel = flg >> 6;
+    ZlibHeaderInfo {
+        window_size: 1u32 << (cinfo as u32 + 8),
+        compression_level: compression_level_label(flevel).to_string(),
+        dictionary_id,
+    }
+}
+
+pub fn read_zlib_chunk<R: Read + Seek>(reader: &mut R, offset: u64, chunk_number: usize) -> io::Result<ChunkInfo> {
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut header = [0u8; ZLIB_HEADER_SIZE];
+    if reader.read_exact(&mut header).is_err() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "End of file"));
+    }
+    if !is_zlib_magic(header) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("Invalid zlib header: {:02x} {:02x}", header[0], header[1])));
+    }
+
+    // FDICT (bit 5 of FLG): a 4-byte big-endian DICTID follows the header.
+    let dictionary_id = if header[1] & 0x20 != 0 {
+        let mut dictid_bytes = [0u8; 4];
+        reader.read_exact(&mut dictid_bytes)?;
+        Some(u32::from_be_bytes(dictid_bytes))
+    } else {
+        None
+    };
+
+    let header_info = parse_zlib_header(header, dictionary_id);
+
+    let (_, decompressed) = consume_deflate_member(reader)?;
+
+    let mut footer = [0u8; ZLIB_TRAILER_SIZE];
+    reader.read_exact(&mut footer)?;
+
+    let compressed_size = reader.stream_position()? - offset;
+
+    let validation = validate_zlib_trailer(&footer, &decompressed).err();
+    let integrity = Some(validation.is_none());
+
+    Ok(ChunkInfo {
+        chunk_number,
+        offset,
+        compressed_size,
+        uncompressed_size: decompressed.len() as u64,
+        compression_ratio: decompressed.len() as f64 / compressed_size as f64,
+        window_size: Some(header_info.window_size),
+        compression_level: Some(header_info.compression_level.clone()),
+        dictionary_id: header_info.dictionary_id,
+        header_info: header_info.to_string(),
+        integrity,
+        validation,
+        bgzf_block_size: None,
+        bgzf_virtual_offset: None,
+        format: Format::Zlib,
+        crc32_expected: None,
+        crc32_actual: None,
+        isize_expected: None,
+        preview: None,
+        preview_data: Some(decompressed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_matches_known_value() {
+        // Wikipedia's worked example for the Adler-32 algorithm.
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn adler32_of_empty_input_is_one() {
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn is_zlib_magic_accepts_default_compression_header() {
+        // 0x78 0x9c is the canonical zlib header for default compression,
+        // the one most real-world deflate libraries emit.
+        assert!(is_zlib_magic([0x78, 0x9c]));
+        assert!(!is_zlib_magic([0x78, 0x00]));
+    }
+}